@@ -0,0 +1,211 @@
+#![allow(clippy::needless_return, clippy::redundant_static_lifetimes)]
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::analysis::sholl::{get_sholl_shells, DEFAULT_SHOLL_STEP};
+use crate::components::Graph;
+
+/// A number computed for every vertex of a neuron.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VertexMetric {
+    /// Distance along the neurites from the root.
+    PathDistance,
+    /// Centrifugal branch order: the number of branch points between the
+    /// soma and a vertex, counting the soma itself.
+    BranchOrder,
+    /// Strahler order: 1 at the tips, increasing where two branches of the
+    /// same order meet.
+    Strahler,
+    Radius,
+    /// Position along the x, y or z axis.
+    Depth(Axis),
+    /// Index of the Sholl shell around the soma, for spheres a given
+    /// distance apart.
+    ShollShell(f64),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+/// Names accepted by `VertexMetric::from_str()`.
+pub static VERTEX_METRIC_NAMES: &'static [&'static str] = &[
+    "path-distance",
+    "branch-order",
+    "strahler",
+    "radius",
+    "x",
+    "y",
+    "z",
+    "sholl-shell",
+];
+
+impl FromStr for VertexMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<VertexMetric, String> {
+        match s {
+            "path-distance" => Ok(VertexMetric::PathDistance),
+            "branch-order" => Ok(VertexMetric::BranchOrder),
+            "strahler" => Ok(VertexMetric::Strahler),
+            "radius" => Ok(VertexMetric::Radius),
+            "x" => Ok(VertexMetric::Depth(Axis::X)),
+            "y" => Ok(VertexMetric::Depth(Axis::Y)),
+            "z" => Ok(VertexMetric::Depth(Axis::Z)),
+            "sholl-shell" => Ok(VertexMetric::ShollShell(DEFAULT_SHOLL_STEP)),
+            _ => Err(format!(
+                "expected one of {}, got `{}`",
+                VERTEX_METRIC_NAMES.join(", "),
+                s
+            )),
+        }
+    }
+}
+
+/// Get the value of a metric for every vertex, keyed by vertex id.
+pub fn compute_vertex_metric(graph: &Graph, metric: VertexMetric) -> BTreeMap<usize, f64> {
+    match metric {
+        VertexMetric::PathDistance => get_path_distances(graph),
+        VertexMetric::BranchOrder => graph
+            .get_branch_orders()
+            .into_iter()
+            .map(|(id, order)| (id, order as f64))
+            .collect(),
+        VertexMetric::Strahler => graph
+            .get_strahler_orders()
+            .into_iter()
+            .map(|(id, order)| (id, order as f64))
+            .collect(),
+        VertexMetric::Radius => graph
+            .iter_vertices()
+            .map(|(id, vertex)| (*id, vertex.get_radius()))
+            .collect(),
+        VertexMetric::Depth(axis) => graph
+            .iter_vertices()
+            .map(|(id, vertex)| {
+                let position = vertex.get_position();
+                let depth = match axis {
+                    Axis::X => position.x,
+                    Axis::Y => position.y,
+                    Axis::Z => position.z,
+                };
+                (*id, depth)
+            })
+            .collect(),
+        VertexMetric::ShollShell(step) => get_sholl_shells(graph, step)
+            .into_iter()
+            .map(|(id, shell)| (id, shell as f64))
+            .collect(),
+    }
+}
+
+/// Get the distance of each vertex from its root along the neurites.
+///
+/// Distances follow the geometry of the compartments, and the whole chain of
+/// segments behind each edge of collapsed graphs (see `Graph::get_segment()`).
+pub fn get_path_distances(graph: &Graph) -> BTreeMap<usize, f64> {
+    let mut distances = BTreeMap::new();
+    for id in graph.get_depth_first_ids() {
+        let distance = match graph.get_vertex(id).unwrap().get_parent_id() {
+            Some(parent_id) => distances[&parent_id] + graph.get_segment(parent_id, id).length,
+            None => 0.0,
+        };
+        distances.insert(id, distance);
+    }
+    return distances;
+}
+
+/// Add the centrifugal branch order and Strahler order of each vertex to its
+/// attributes as `branch_order` and `strahler_order`.
+pub fn add_order_attributes(graph: &mut Graph) {
+    let branch_orders = graph.get_branch_orders();
+    let strahler_orders = graph.get_strahler_orders();
+    for (id, order) in branch_orders {
+        graph.set_vertex_number(id, "branch_order", order as f64);
+    }
+    for (id, order) in strahler_orders {
+        graph.set_vertex_number(id, "strahler_order", order as f64);
+    }
+}
+
+#[cfg(test)]
+mod metrics_tests {
+    use super::*;
+    use crate::test_utils::{self, graph_from_swc};
+
+    /// A soma with one dendrite that forks twice.
+    ///
+    /// ```text
+    /// 1 (soma) - 2 - 3 -+- 4
+    ///                   +- 5 -+- 6
+    ///                         +- 7
+    /// ```
+    fn get_test_graph() -> Graph {
+        let swc = "1 1 0 0 0 5 -1\n\
+                   2 3 3 4 0 1 1\n\
+                   3 3 3 8 0 1 2\n\
+                   4 3 3 9 0 1 3\n\
+                   5 3 3 10 0 0.5 3\n\
+                   6 3 3 11 0 0.5 5\n\
+                   7 3 3 12 0 0.5 5\n";
+        return graph_from_swc(swc);
+    }
+
+    #[test]
+    fn path_distance() {
+        let distances = get_path_distances(&get_test_graph());
+        assert_eq!(distances[&1], 0.0);
+        assert_eq!(distances[&2], 5.0);
+        assert_eq!(distances[&3], 9.0);
+        assert_eq!(distances[&7], 13.0);
+    }
+
+    #[test]
+    fn path_distances_follow_collapsed_chains() {
+        let collapsed = test_utils::get_test_graph().collapse();
+        let distances: Vec<(usize, f64)> = get_path_distances(&collapsed).into_iter().collect();
+        assert_eq!(distances, vec![(1, 0.0), (3, 7.0), (4, 4.0)]);
+    }
+
+    #[test]
+    fn path_distances_ignore_length_attributes() {
+        let swc = "1 1 0 0 0 1 -1\n2 3 0 0.00004 0 1 1\n3 3 0 0.00008 0 1 2\n";
+        let mut graph = graph_from_swc(swc);
+        graph.set_edge_attribute(1, 2, "length", "10".to_string());
+        crate::analysis::add_segment_attributes(&mut graph, true, None);
+        assert_eq!(get_path_distances(&graph)[&3], 0.00008);
+        assert_eq!(get_path_distances(&graph.collapse())[&3], 0.00008);
+    }
+
+    #[test]
+    fn depth() {
+        let depths = compute_vertex_metric(&get_test_graph(), VertexMetric::Depth(Axis::Y));
+        assert_eq!(depths[&4], 9.0);
+    }
+
+    #[test]
+    fn parse_metric_names() {
+        for name in VERTEX_METRIC_NAMES.iter() {
+            assert!(
+                name.parse::<VertexMetric>().is_ok(),
+                "Could not parse {}",
+                name
+            );
+        }
+        assert!("volume".parse::<VertexMetric>().is_err());
+    }
+
+    #[test]
+    fn order_attributes() {
+        let mut graph = get_test_graph();
+        add_order_attributes(&mut graph);
+        let attributes = graph.get_vertex(5).unwrap().get_attributes();
+        assert_eq!(attributes.get("branch_order"), Some(&"2".to_string()));
+        assert_eq!(attributes.get("strahler_order"), Some(&"2".to_string()));
+        assert!(attributes.is_number("branch_order") && attributes.is_number("strahler_order"));
+    }
+}
@@ -0,0 +1,9 @@
+mod metrics;
+mod segment;
+mod sholl;
+mod stats;
+
+pub use metrics::{add_order_attributes, compute_vertex_metric, get_path_distances, VertexMetric};
+pub use segment::{add_segment_attributes, Segment};
+pub use sholl::{get_sholl_shells, ShollProfile, DEFAULT_SHOLL_STEP};
+pub use stats::{KindMorphometrics, Morphometrics};
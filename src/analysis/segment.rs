@@ -0,0 +1,152 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use std::f64::consts::PI;
+
+use crate::components::{Graph, Vertex};
+
+/// The tapered cylinder (conical frustum) joining a compartment to its parent.
+///
+/// Distances are in SWC units (usually microns).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Segment {
+    /// Distance between the centres of the two compartments.
+    pub length: f64,
+    /// Mean of the radii at the two ends.
+    pub mean_radius: f64,
+    /// Lateral surface area, not counting the ends.
+    pub surface_area: f64,
+    pub volume: f64,
+}
+
+impl Segment {
+    /// Get the segment between a vertex and one of its children.
+    pub fn between(parent: &Vertex, child: &Vertex) -> Segment {
+        let (start, end) = (parent.get_position(), child.get_position());
+        let length =
+            ((end.x - start.x).powi(2) + (end.y - start.y).powi(2) + (end.z - start.z).powi(2))
+                .sqrt();
+        let (r1, r2) = (parent.get_radius(), child.get_radius());
+        let slant_height = ((r1 - r2).powi(2) + length.powi(2)).sqrt();
+        Segment {
+            length: length,
+            mean_radius: (r1 + r2) / 2.0,
+            surface_area: PI * (r1 + r2) * slant_height,
+            volume: PI * length * (r1 * r1 + r1 * r2 + r2 * r2) / 3.0,
+        }
+    }
+}
+
+/// Add the geometry of each segment, or of each whole chain in collapsed
+/// graphs (see `Graph::get_segment()`), to the attributes of its edge.
+///
+/// If `metrics` is set, `length`, `mean_radius`, `surface_area` and `volume`
+/// attributes are added. If `len_scale` is given, segment length is also used
+/// as the Graphviz `len` attribute (the preferred edge length in neato
+/// layouts), at `len_scale` points per SWC unit.
+pub fn add_segment_attributes(graph: &mut Graph, metrics: bool, len_scale: Option<f64>) {
+    let segments: Vec<(usize, usize, Segment)> = graph
+        .iter_edges()
+        .map(|(parent_id, child_id)| (parent_id, child_id, graph.get_segment(parent_id, child_id)))
+        .collect();
+
+    for (parent_id, child_id, segment) in segments {
+        if metrics {
+            let attributes = [
+                ("length", segment.length),
+                ("mean_radius", segment.mean_radius),
+                ("surface_area", segment.surface_area),
+                ("volume", segment.volume),
+            ];
+            for (key, value) in attributes.iter() {
+                graph.set_edge_number(parent_id, child_id, key, *value);
+            }
+        }
+        if let Some(len_scale) = len_scale {
+            // Graphviz measures `len` in inches.
+            graph.set_edge_number(parent_id, child_id, "len", segment.length * len_scale / 72.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod segment_tests {
+    use super::*;
+    use crate::test_utils::{self, graph_from_swc};
+
+    fn get_test_graph() -> Graph {
+        // A cone of height 4 and a cylinder of radius 1 and height 3.
+        let swc = "1 1 0 0 0 2 -1\n\
+                   2 3 0 0 4 0 1\n\
+                   3 3 0 0 0 1 1\n\
+                   4 3 0 3 0 1 3\n";
+        return graph_from_swc(swc);
+    }
+
+    fn get_segment(graph: &Graph, parent_id: usize, child_id: usize) -> Segment {
+        Segment::between(
+            graph.get_vertex(parent_id).unwrap(),
+            graph.get_vertex(child_id).unwrap(),
+        )
+    }
+
+    #[test]
+    fn cone() {
+        let segment = get_segment(&get_test_graph(), 1, 2);
+        assert_eq!(segment.length, 4.0);
+        assert_eq!(segment.mean_radius, 1.0);
+        // Lateral area of a cone is pi * r * slant height.
+        assert!((segment.surface_area - PI * 2.0 * 20.0_f64.sqrt()).abs() < 1e-12);
+        assert!((segment.volume - PI * 4.0 * 4.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cylinder() {
+        let segment = get_segment(&get_test_graph(), 3, 4);
+        assert_eq!(segment.length, 3.0);
+        assert!((segment.surface_area - 2.0 * PI * 3.0).abs() < 1e-12);
+        assert!((segment.volume - PI * 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn metrics_are_added_to_every_edge() {
+        let mut graph = get_test_graph();
+        add_segment_attributes(&mut graph, true, None);
+        let attributes = graph.get_edge_attributes(3, 4).unwrap();
+        let entries: Vec<(&String, &String)> = attributes.iter().collect();
+        assert_eq!(
+            entries,
+            vec![
+                (&"length".to_string(), &"3".to_string()),
+                (&"mean_radius".to_string(), &"1".to_string()),
+                (&"surface_area".to_string(), &"18.8496".to_string()),
+                (&"volume".to_string(), &"9.4248".to_string()),
+            ]
+        );
+        for (parent_id, child_id) in graph.iter_edges() {
+            let attributes = graph.get_edge_attributes(parent_id, child_id).unwrap();
+            for key in ["length", "mean_radius", "surface_area", "volume"].iter() {
+                assert!(attributes.is_number(key), "Missing {} number", key);
+            }
+        }
+    }
+
+    #[test]
+    fn length_as_graphviz_len() {
+        let mut graph = get_test_graph();
+        add_segment_attributes(&mut graph, false, Some(24.0));
+        let attributes = graph.get_edge_attributes(3, 4).unwrap();
+        assert_eq!(attributes.iter().count(), 1);
+        assert_eq!(attributes.get("len"), Some(&"1".to_string()));
+        assert!(attributes.is_number("len"));
+    }
+
+    #[test]
+    fn collapsed_edges_get_chain_metrics() {
+        let mut graph = test_utils::get_test_graph().collapse();
+        add_segment_attributes(&mut graph, true, Some(72.0));
+        let attributes = graph.get_edge_attributes(1, 3).unwrap();
+        assert_eq!(attributes.get("length"), Some(&"7".to_string()));
+        assert_eq!(attributes.get("mean_radius"), Some(&"1.0714".to_string()));
+        assert_eq!(attributes.get("len"), Some(&"7".to_string()));
+    }
+}
@@ -0,0 +1,186 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use std::collections::BTreeMap;
+
+use crate::components::Graph;
+use crate::swc_parser::{Point, SWCCompartmentKind};
+
+/// Default distance between Sholl spheres, in SWC units (usually microns).
+pub static DEFAULT_SHOLL_STEP: f64 = 10.0;
+
+/// Largest number of spheres in a Sholl profile, which keeps a tiny step from
+/// exhausting memory.
+pub static MAX_SHOLL_SPHERES: usize = 1_000_000;
+
+/// Number of times the neurites cross concentric spheres around the soma.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShollProfile {
+    /// Centre of the spheres.
+    pub center: Point,
+    /// Radius of each sphere, in increasing order.
+    pub radii: Vec<f64>,
+    /// Number of neurite segments crossing each sphere.
+    pub intersections: Vec<usize>,
+    /// Intersections of each kind of neurite present in the neuron, with one
+    /// entry per sphere. Segments count towards the kind of their distal end.
+    pub kinds: BTreeMap<SWCCompartmentKind, Vec<usize>>,
+}
+
+impl ShollProfile {
+    /// Count intersections with spheres every `step` units around the soma,
+    /// out to the furthest compartment.
+    ///
+    /// Returns an error if that takes more than `MAX_SHOLL_SPHERES` spheres.
+    pub fn measure(graph: &Graph, step: f64) -> Result<ShollProfile, String> {
+        let center = get_soma_centroid(graph);
+        let distances: BTreeMap<usize, f64> = graph
+            .iter_vertices()
+            .map(|(id, vertex)| (*id, get_distance(&center, &vertex.get_position())))
+            .collect();
+        let furthest = distances.values().fold(0.0, |a: f64, b| a.max(*b));
+        let sphere_count = (furthest / step).floor();
+        if sphere_count > MAX_SHOLL_SPHERES as f64 {
+            return Err(format!(
+                "a Sholl step of {} needs more than {} spheres to reach the furthest \
+                 compartment, {} units from the soma; use a larger step",
+                step, MAX_SHOLL_SPHERES, furthest
+            ));
+        }
+        let sphere_count = sphere_count as usize;
+
+        let mut intersections = vec![0; sphere_count];
+        let mut kinds: BTreeMap<SWCCompartmentKind, Vec<usize>> = BTreeMap::new();
+        for (parent_id, child_id) in graph.iter_edges() {
+            let kind = graph.get_vertex(child_id).unwrap().get_kind();
+            if kind == SWCCompartmentKind::Soma {
+                continue;
+            }
+            let kind_intersections = kinds.entry(kind).or_insert_with(|| vec![0; sphere_count]);
+            let (near, far) = if distances[&parent_id] < distances[&child_id] {
+                (distances[&parent_id], distances[&child_id])
+            } else {
+                (distances[&child_id], distances[&parent_id])
+            };
+            // Sphere `i` has radius `(i + 1) * step` and is crossed if
+            // `near < radius <= far`.
+            let first = (near / step).floor() as usize;
+            let last = ((far / step).floor() as usize).min(sphere_count);
+            for i in first..last {
+                intersections[i] += 1;
+                kind_intersections[i] += 1;
+            }
+        }
+
+        Ok(ShollProfile {
+            center: center,
+            radii: (1..=sphere_count).map(|i| i as f64 * step).collect(),
+            intersections: intersections,
+            kinds: kinds,
+        })
+    }
+}
+
+/// Get the index of the Sholl shell each vertex lies in.
+///
+/// Shell `i` lies between spheres of radius `i * step` and `(i + 1) * step`
+/// around the soma.
+pub fn get_sholl_shells(graph: &Graph, step: f64) -> BTreeMap<usize, usize> {
+    let center = get_soma_centroid(graph);
+    graph
+        .iter_vertices()
+        .map(|(id, vertex)| {
+            let distance = get_distance(&center, &vertex.get_position());
+            (*id, (distance / step).floor() as usize)
+        })
+        .collect()
+}
+
+/// Get the mean position of the somatic compartments.
+///
+/// Falls back on the position of the first root if there is no soma.
+fn get_soma_centroid(graph: &Graph) -> Point {
+    let soma_positions: Vec<Point> = graph
+        .iter_vertices()
+        .map(|(_, vertex)| vertex)
+        .filter(|vertex| vertex.get_kind() == SWCCompartmentKind::Soma)
+        .map(|vertex| vertex.get_position())
+        .collect();
+    if soma_positions.is_empty() {
+        return match graph.get_root_ids().first() {
+            Some(root_id) => graph.get_vertex(*root_id).unwrap().get_position(),
+            None => Point {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        };
+    }
+    let count = soma_positions.len() as f64;
+    Point {
+        x: soma_positions.iter().map(|p| p.x).sum::<f64>() / count,
+        y: soma_positions.iter().map(|p| p.y).sum::<f64>() / count,
+        z: soma_positions.iter().map(|p| p.z).sum::<f64>() / count,
+    }
+}
+
+fn get_distance(a: &Point, b: &Point) -> f64 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod sholl_tests {
+    use super::*;
+    use crate::test_utils::graph_from_swc;
+
+    /// A soma at the origin with a dendrite forking at 15 and an axon
+    /// reaching out to 25.
+    fn get_test_graph() -> Graph {
+        let swc = "1 1 0 0 0 5 -1\n\
+                   2 3 0 15 0 1 1\n\
+                   3 3 -8 21 0 1 2\n\
+                   4 3 8 21 0 1 2\n\
+                   5 2 0 -25 0 1 1\n";
+        return graph_from_swc(swc);
+    }
+
+    fn get_test_profile() -> ShollProfile {
+        ShollProfile::measure(&get_test_graph(), 10.0).expect("Could not measure test profile.")
+    }
+
+    #[test]
+    fn intersections() {
+        let profile = get_test_profile();
+        assert_eq!(profile.radii, vec![10.0, 20.0]);
+        assert_eq!(profile.intersections, vec![2, 3]);
+    }
+
+    #[test]
+    fn intersections_by_kind() {
+        let profile = get_test_profile();
+        assert_eq!(profile.kinds[&SWCCompartmentKind::Dendrite], vec![1, 2]);
+        assert_eq!(profile.kinds[&SWCCompartmentKind::Axon], vec![1, 1]);
+        assert!(!profile.kinds.contains_key(&SWCCompartmentKind::Soma));
+    }
+
+    #[test]
+    fn tiny_step_is_an_error() {
+        assert!(ShollProfile::measure(&get_test_graph(), 1e-12).is_err());
+        assert!(ShollProfile::measure(&get_test_graph(), 1e-4).is_ok());
+    }
+
+    #[test]
+    fn shells() {
+        let shells = get_sholl_shells(&get_test_graph(), 10.0);
+        let expected: BTreeMap<usize, usize> = vec![(1, 0), (2, 1), (3, 2), (4, 2), (5, 2)]
+            .into_iter()
+            .collect();
+        assert_eq!(shells, expected);
+    }
+
+    #[test]
+    fn center_is_soma_centroid() {
+        let swc = "1 1 0 0 0 1 -1\n2 1 2 4 0 1 1\n3 3 1 9 0 1 2\n";
+        let center = get_soma_centroid(&graph_from_swc(swc));
+        assert_eq!((center.x, center.y, center.z), (1.0, 2.0, 0.0));
+    }
+}
@@ -0,0 +1,218 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use std::collections::BTreeMap;
+
+use crate::analysis::metrics::get_path_distances;
+use crate::analysis::Segment;
+use crate::components::Graph;
+use crate::swc_parser::{Point, SWCCompartmentKind};
+
+/// Summary measurements of a whole neuron.
+///
+/// Lengths, areas and volumes are in SWC units (usually microns) and only
+/// count neurites. Each segment belongs to the kind of the compartment at its
+/// distal end, so the segment joining a dendrite to the soma is dendritic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Morphometrics {
+    pub compartments: usize,
+    pub total_length: f64,
+    /// Number of neurite compartments with more than one child.
+    pub branch_points: usize,
+    /// Number of neurite compartments without children.
+    pub terminals: usize,
+    /// Number of neurites leaving the soma.
+    pub stems: usize,
+    pub max_branch_order: usize,
+    /// Strahler order of the root, which is the largest in the neuron.
+    pub max_strahler_order: usize,
+    /// Largest distance from the root along the neurites.
+    pub max_path_distance: f64,
+    pub total_surface_area: f64,
+    pub total_volume: f64,
+    /// Mean radius of the somatic compartments, if there are any.
+    pub soma_radius: Option<f64>,
+    /// Smallest and largest corner of the box around all compartments.
+    pub bounding_box: Option<(Point, Point)>,
+    /// Measurements of each kind of compartment present in the neuron.
+    pub kinds: BTreeMap<SWCCompartmentKind, KindMorphometrics>,
+}
+
+/// Summary measurements of the compartments of one kind.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KindMorphometrics {
+    pub compartments: usize,
+    pub length: f64,
+    pub surface_area: f64,
+    pub volume: f64,
+    pub branch_points: usize,
+    pub terminals: usize,
+}
+
+impl Morphometrics {
+    /// Measure a neuron.
+    pub fn measure(graph: &Graph) -> Morphometrics {
+        let mut kinds: BTreeMap<SWCCompartmentKind, KindMorphometrics> = BTreeMap::new();
+        let mut stems = 0;
+        let mut soma_radii = Vec::new();
+        let mut bounding_box: Option<(Point, Point)> = None;
+
+        for (_, vertex) in graph.iter_vertices() {
+            let kind = vertex.get_kind();
+            let kind_metrics = kinds.entry(kind).or_default();
+            kind_metrics.compartments += 1;
+
+            let parent = vertex
+                .get_parent_id()
+                .map(|parent_id| graph.get_vertex(parent_id).unwrap());
+            if let Some(parent) = parent {
+                let segment = Segment::between(parent, vertex);
+                kind_metrics.length += segment.length;
+                kind_metrics.surface_area += segment.surface_area;
+                kind_metrics.volume += segment.volume;
+            }
+
+            if kind == SWCCompartmentKind::Soma {
+                soma_radii.push(vertex.get_radius());
+            } else {
+                match vertex.get_child_ids().len() {
+                    0 => kind_metrics.terminals += 1,
+                    1 => {}
+                    _ => kind_metrics.branch_points += 1,
+                }
+                let is_stem = match parent {
+                    Some(parent) => parent.get_kind() == SWCCompartmentKind::Soma,
+                    None => true,
+                };
+                if is_stem {
+                    stems += 1;
+                }
+            }
+
+            let position = vertex.get_position();
+            bounding_box = Some(match bounding_box {
+                Some((min, max)) => (
+                    Point {
+                        x: min.x.min(position.x),
+                        y: min.y.min(position.y),
+                        z: min.z.min(position.z),
+                    },
+                    Point {
+                        x: max.x.max(position.x),
+                        y: max.y.max(position.y),
+                        z: max.z.max(position.z),
+                    },
+                ),
+                None => (position, position),
+            });
+        }
+
+        let neurites = kinds
+            .iter()
+            .filter(|(kind, _)| **kind != SWCCompartmentKind::Soma)
+            .map(|(_, kind_metrics)| kind_metrics);
+        let (mut total_length, mut total_surface_area, mut total_volume) = (0.0, 0.0, 0.0);
+        let (mut branch_points, mut terminals) = (0, 0);
+        for kind_metrics in neurites {
+            total_length += kind_metrics.length;
+            total_surface_area += kind_metrics.surface_area;
+            total_volume += kind_metrics.volume;
+            branch_points += kind_metrics.branch_points;
+            terminals += kind_metrics.terminals;
+        }
+
+        let soma_radius = if soma_radii.is_empty() {
+            None
+        } else {
+            Some(soma_radii.iter().sum::<f64>() / soma_radii.len() as f64)
+        };
+
+        Morphometrics {
+            compartments: graph.len(),
+            total_length: total_length,
+            branch_points: branch_points,
+            terminals: terminals,
+            stems: stems,
+            max_branch_order: graph
+                .get_branch_orders()
+                .values()
+                .max()
+                .copied()
+                .unwrap_or(0),
+            max_strahler_order: graph
+                .get_strahler_orders()
+                .values()
+                .max()
+                .copied()
+                .unwrap_or(0),
+            max_path_distance: get_path_distances(graph)
+                .values()
+                .fold(0.0, |a, b| a.max(*b)),
+            total_surface_area: total_surface_area,
+            total_volume: total_volume,
+            soma_radius: soma_radius,
+            bounding_box: bounding_box,
+            kinds: kinds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod morphometrics_tests {
+    use super::*;
+    use crate::test_utils::graph_from_swc;
+
+    /// A soma with a forked dendrite and an unbranched axon.
+    fn get_test_morphometrics() -> Morphometrics {
+        let swc = "1 1 0 0 0 2 -1\n\
+                   2 3 0 3 0 1 1\n\
+                   3 3 0 5 0 1 2\n\
+                   4 3 0 6 0 1 3\n\
+                   5 3 0 7 0 1 3\n\
+                   6 2 0 -4 0 1 1\n\
+                   7 2 0 -4 -2 1 6\n";
+        return Morphometrics::measure(&graph_from_swc(swc));
+    }
+
+    #[test]
+    fn topology() {
+        let metrics = get_test_morphometrics();
+        assert_eq!(metrics.compartments, 7);
+        assert_eq!(metrics.branch_points, 1);
+        assert_eq!(metrics.terminals, 3);
+        assert_eq!(metrics.stems, 2);
+        assert_eq!(metrics.max_branch_order, 2);
+        assert_eq!(metrics.max_strahler_order, 2);
+    }
+
+    #[test]
+    fn lengths() {
+        let metrics = get_test_morphometrics();
+        assert_eq!(metrics.total_length, 14.0);
+        assert_eq!(metrics.kinds[&SWCCompartmentKind::Dendrite].length, 8.0);
+        assert_eq!(metrics.kinds[&SWCCompartmentKind::Axon].length, 6.0);
+        assert_eq!(metrics.max_path_distance, 7.0);
+    }
+
+    #[test]
+    fn soma_and_bounding_box() {
+        let metrics = get_test_morphometrics();
+        assert_eq!(metrics.soma_radius, Some(2.0));
+        let (min, max) = metrics.bounding_box.unwrap();
+        assert_eq!((min.x, min.y, min.z), (0.0, -4.0, -2.0));
+        assert_eq!((max.x, max.y, max.z), (0.0, 7.0, 0.0));
+    }
+
+    #[test]
+    fn only_present_kinds_are_listed() {
+        let kinds: Vec<SWCCompartmentKind> =
+            get_test_morphometrics().kinds.keys().copied().collect();
+        assert_eq!(
+            kinds,
+            vec![
+                SWCCompartmentKind::Soma,
+                SWCCompartmentKind::Axon,
+                SWCCompartmentKind::Dendrite
+            ]
+        );
+    }
+}
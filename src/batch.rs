@@ -0,0 +1,425 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::read_dir;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
+use std::thread;
+
+use glob::glob;
+
+/// An SWC file to convert as part of a batch.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InputFile {
+    /// Path to the file, as given on the command line or found on disk.
+    pub path: PathBuf,
+    /// Path of the file relative to the directory or glob it was found in.
+    ///
+    /// Used to mirror the input directory structure in the output directory.
+    pub relative_path: PathBuf,
+}
+
+impl InputFile {
+    fn new(path: PathBuf, base: &Path) -> InputFile {
+        let relative_path = match path.strip_prefix(base) {
+            Ok(relative_path) if relative_path.components().next().is_some() => {
+                relative_path.to_path_buf()
+            }
+            _ => PathBuf::from(path.file_name().unwrap_or_else(|| path.as_os_str())),
+        };
+        InputFile {
+            path: path,
+            relative_path: relative_path,
+        }
+    }
+
+    /// Get the path of the output file for this input.
+    ///
+    /// The output is placed next to the input file, or at the same relative
+    /// location inside `output_dir` if one is given, with `suffix` in place of
+    /// the file extension.
+    pub fn get_output_path(&self, output_dir: Option<&Path>, suffix: &str) -> PathBuf {
+        let output_path = match output_dir {
+            Some(output_dir) => output_dir.join(&self.relative_path),
+            None => self.path.clone(),
+        };
+        let mut output_file_name = output_path.file_stem().unwrap_or_default().to_os_string();
+        output_file_name.push(suffix);
+        return output_path.with_file_name(output_file_name);
+    }
+}
+
+/// Get the output path of every input file (see `InputFile::get_output_path()`).
+///
+/// Returns an error if two inputs would be written to the same output, such
+/// as files with the same name in different directories given separately
+/// along with `output_dir`.
+pub fn get_output_paths(
+    input_files: &[InputFile],
+    output_dir: Option<&Path>,
+    suffix: &str,
+) -> Result<Vec<PathBuf>, String> {
+    let mut inputs_by_output = BTreeMap::<PathBuf, &Path>::new();
+    let mut output_paths = Vec::with_capacity(input_files.len());
+    for input_file in input_files {
+        let output_path = input_file.get_output_path(output_dir, suffix);
+        if let Some(other_input) = inputs_by_output.insert(output_path.clone(), &input_file.path) {
+            return Err(format!(
+                "{} and {} would both be written to {}",
+                other_input.display(),
+                input_file.path.display(),
+                output_path.display()
+            ));
+        }
+        output_paths.push(output_path);
+    }
+    return Ok(output_paths);
+}
+
+/// Find the SWC files referred to by a list of files, directories and glob patterns.
+///
+/// Directories are searched recursively for files with a `.swc` extension
+/// (in any case). Files named explicitly are used whatever their extension.
+/// The result is sorted by path and contains no duplicates.
+pub fn collect_input_files(specs: &[&str]) -> Result<Vec<InputFile>, String> {
+    let mut input_files = BTreeSet::<InputFile>::new();
+
+    for spec in specs {
+        let path = Path::new(spec);
+        if path.is_dir() {
+            let mut found = Vec::new();
+            find_swc_files(path, &mut found)?;
+            input_files.extend(found.into_iter().map(|file| InputFile::new(file, path)));
+        } else if path.is_file() {
+            let base = path.parent().unwrap_or_else(|| Path::new(""));
+            input_files.insert(InputFile::new(path.to_path_buf(), base));
+        } else if is_glob(spec) {
+            let matches = match glob(spec) {
+                Ok(matches) => matches,
+                Err(err) => return Err(format!("invalid pattern `{}`: {}", spec, err)),
+            };
+            let base = get_glob_base(spec);
+            let mut matched_any = false;
+            for entry in matches {
+                match entry {
+                    Ok(file) if file.is_file() => {
+                        input_files.insert(InputFile::new(file, &base));
+                        matched_any = true;
+                    }
+                    Ok(_) => {}
+                    Err(err) => return Err(format!("could not read {}", err)),
+                }
+            }
+            if !matched_any {
+                return Err(format!("no files match `{}`", spec));
+            }
+        } else {
+            return Err(format!("{} does not exist", spec));
+        }
+    }
+
+    return Ok(input_files.into_iter().collect());
+}
+
+/// Recursively find files with a `.swc` extension in a directory.
+fn find_swc_files(dir: &Path, found: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = match read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            return Err(format!(
+                "could not read directory {}: {}",
+                dir.display(),
+                err
+            ))
+        }
+    };
+    for entry in entries {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(err) => {
+                return Err(format!(
+                    "could not read directory {}: {}",
+                    dir.display(),
+                    err
+                ))
+            }
+        };
+        if path.is_dir() {
+            find_swc_files(&path, found)?;
+        } else if is_swc_file(&path) {
+            found.push(path);
+        }
+    }
+    return Ok(());
+}
+
+fn is_swc_file(path: &Path) -> bool {
+    match path.extension() {
+        Some(extension) => extension.to_string_lossy().eq_ignore_ascii_case("swc"),
+        None => false,
+    }
+}
+
+fn is_glob(spec: &str) -> bool {
+    spec.contains(['*', '?', '['])
+}
+
+/// Get the directory part of a glob pattern that comes before any wildcards.
+fn get_glob_base(spec: &str) -> PathBuf {
+    let mut base = PathBuf::new();
+    for component in Path::new(spec).components() {
+        if let Component::Normal(part) = component {
+            if is_glob(&part.to_string_lossy()) {
+                break;
+            }
+        }
+        base.push(component);
+    }
+    return base;
+}
+
+/// Apply `job` to every item using up to `jobs` worker threads.
+///
+/// `on_result` is called on the calling thread with the index and result of
+/// each item in the same order as `items`, no matter which order the workers
+/// finish in, so anything it prints or writes is deterministic.
+pub fn run_in_order<T, R, F, G>(items: &[T], jobs: usize, job: F, mut on_result: G)
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+    G: FnMut(usize, R),
+{
+    let jobs = jobs.max(1).min(items.len());
+    if jobs <= 1 {
+        for (i, item) in items.iter().enumerate() {
+            on_result(i, job(item));
+        }
+        return;
+    }
+
+    let next_item = AtomicUsize::new(0);
+    let (sender, receiver) = channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let (next_item, job) = (&next_item, &job);
+            scope.spawn(move || loop {
+                let i = next_item.fetch_add(1, Ordering::SeqCst);
+                if i >= items.len() {
+                    break;
+                }
+                if sender.send((i, job(&items[i]))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        // Hold on to results that arrive early until it's their turn.
+        let mut pending = BTreeMap::new();
+        let mut next_result = 0;
+        for (i, result) in receiver.iter() {
+            pending.insert(i, result);
+            while let Some(result) = pending.remove(&next_result) {
+                on_result(next_result, result);
+                next_result += 1;
+            }
+        }
+    });
+}
+
+/// Successes and failures from converting a batch of files.
+pub struct BatchSummary {
+    succeeded: usize,
+    failed: Vec<(PathBuf, String)>,
+}
+
+impl BatchSummary {
+    pub fn new() -> BatchSummary {
+        BatchSummary {
+            succeeded: 0,
+            failed: Vec::new(),
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        self.succeeded += 1;
+    }
+
+    pub fn record_failure(&mut self, path: &Path, reason: String) {
+        self.failed.push((path.to_path_buf(), reason));
+    }
+
+    pub fn has_failures(&self) -> bool {
+        !self.failed.is_empty()
+    }
+
+    /// Get a human-readable summary of the batch.
+    #[allow(clippy::inherent_to_string)]
+    pub fn to_string(&self) -> String {
+        let mut summary = format!(
+            "converted {} of {} file(s)",
+            self.succeeded,
+            self.succeeded + self.failed.len()
+        );
+        if self.has_failures() {
+            summary.push_str(&format!("; {} failed:", self.failed.len()));
+            for (path, reason) in self.failed.iter() {
+                summary.push_str(&format!("\n    {}: {}", path.display(), reason));
+            }
+        }
+        return summary;
+    }
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use super::*;
+    use std::fs::{create_dir_all, remove_dir_all, File};
+
+    /// Create an empty directory tree for testing, removing any old one.
+    fn make_test_dir(name: &str, files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("swc2dot_{}_{}", name, std::process::id()));
+        if dir.exists() {
+            remove_dir_all(&dir).expect("Could not remove old test directory.");
+        }
+        for file in files {
+            let path = dir.join(file);
+            create_dir_all(path.parent().unwrap()).expect("Could not create test directory.");
+            File::create(path).expect("Could not create test file.");
+        }
+        return dir;
+    }
+
+    #[test]
+    fn glob_base() {
+        assert_eq!(
+            get_glob_base("data/cells/*.swc"),
+            PathBuf::from("data/cells")
+        );
+        assert_eq!(get_glob_base("data/**/*.swc"), PathBuf::from("data"));
+        assert_eq!(get_glob_base("*.swc"), PathBuf::from(""));
+    }
+
+    #[test]
+    fn output_next_to_input() {
+        let input = InputFile::new(PathBuf::from("data/a/cell.swc"), Path::new("data"));
+        assert_eq!(
+            input.get_output_path(None, ".dot"),
+            PathBuf::from("data/a/cell.dot")
+        );
+    }
+
+    #[test]
+    fn output_dir_mirrors_input_dir() {
+        let input = InputFile::new(PathBuf::from("data/a/cell.swc"), Path::new("data"));
+        assert_eq!(
+            input.get_output_path(Some(Path::new("out")), ".dot"),
+            PathBuf::from("out/a/cell.dot")
+        );
+    }
+
+    #[test]
+    fn extension_is_replaced_in_file_name_only() {
+        let input = InputFile::new(PathBuf::from("../v1.0/cell"), Path::new("../v1.0"));
+        assert_eq!(
+            input.get_output_path(Some(Path::new("../out")), ".dot"),
+            PathBuf::from("../out/cell.dot")
+        );
+        assert_eq!(
+            input.get_output_path(None, ".dot"),
+            PathBuf::from("../v1.0/cell.dot")
+        );
+        let input = InputFile::new(PathBuf::from("v1.0/cell.v2.swc"), Path::new("v1.0"));
+        assert_eq!(
+            input.get_output_path(None, "_standardized.swc"),
+            PathBuf::from("v1.0/cell.v2_standardized.swc")
+        );
+    }
+
+    #[test]
+    fn inputs_with_the_same_output_are_error() {
+        let dir = make_test_dir("same_output", &["a/cell.swc", "b/cell.swc"]);
+        let inputs = collect_input_files(&[
+            &dir.join("a").to_string_lossy(),
+            &dir.join("b").to_string_lossy(),
+        ])
+        .unwrap();
+        let output_dir = dir.join("out");
+        let err = get_output_paths(&inputs, Some(&output_dir), ".dot").unwrap_err();
+        assert!(err.contains(&output_dir.join("cell.dot").display().to_string()));
+        assert_eq!(get_output_paths(&inputs, None, ".dot").unwrap().len(), 2);
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn directories_are_searched_recursively() {
+        let dir = make_test_dir(
+            "recursive",
+            &["a.swc", "sub/b.SWC", "sub/deeper/c.swc", "notes.txt"],
+        );
+        let inputs = collect_input_files(&[&dir.to_string_lossy()]).unwrap();
+        let relative_paths: Vec<PathBuf> = inputs
+            .into_iter()
+            .map(|input| input.relative_path)
+            .collect();
+        assert_eq!(
+            relative_paths,
+            vec![
+                PathBuf::from("a.swc"),
+                PathBuf::from("sub/b.SWC"),
+                PathBuf::from("sub/deeper/c.swc")
+            ]
+        );
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn glob_patterns_are_expanded() {
+        let dir = make_test_dir("glob", &["a.swc", "b.swc", "sub/c.swc"]);
+        let pattern = dir.join("*.swc");
+        let inputs = collect_input_files(&[&pattern.to_string_lossy()]).unwrap();
+        assert_eq!(inputs.len(), 2);
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn duplicate_inputs_are_removed() {
+        let dir = make_test_dir("duplicates", &["a.swc"]);
+        let file = dir.join("a.swc");
+        let inputs =
+            collect_input_files(&[&file.to_string_lossy(), &file.to_string_lossy()]).unwrap();
+        assert_eq!(inputs.len(), 1);
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_input_is_error() {
+        assert!(collect_input_files(&["/this/file/does/not/exist.swc"]).is_err());
+    }
+
+    #[test]
+    fn results_are_in_input_order() {
+        let items: Vec<u64> = (0..50).collect();
+        for jobs in [1, 4, 100].iter() {
+            let mut results = Vec::new();
+            run_in_order(
+                &items,
+                *jobs,
+                |item| {
+                    // Make early items finish last.
+                    thread::sleep(std::time::Duration::from_micros((50 - item) * 100));
+                    item * 2
+                },
+                |i, result| results.push((i, result)),
+            );
+            let expected: Vec<(usize, u64)> = items
+                .iter()
+                .map(|item| (*item as usize, item * 2))
+                .collect();
+            assert_eq!(results, expected, "Results out of order with {} jobs", jobs);
+        }
+    }
+}
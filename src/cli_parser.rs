@@ -1,24 +1,63 @@
-use clap::{App, Arg, ArgMatches};
+#![allow(clippy::needless_return)]
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+use crate::analysis::VertexMetric;
+use crate::writer::{Colormap, Projection, RadiusMapping};
 
 pub fn get_cli_arguments<'a>() -> ArgMatches<'a> {
+    get_app().get_matches()
+}
+
+/// Get the command line interface, without parsing any arguments.
+fn get_app<'a, 'b>() -> App<'a, 'b> {
     App::new("swc2dot")
         .version("0.1.2")
         .author("Emerson Harkin <emerson.f.harkin@gmail.com>")
         .about("Convert SWC neuron morphologies to DOT graph language.")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::with_name("output")
                 .short("o")
                 .long("output")
-                .help("Output file for morphology in DOT format")
+                .help("Output file for the converted morphology. Use `-` for stdout")
                 .value_name("FILE")
                 .takes_value(true),
         )
         .arg(
-            Arg::with_name("INPUT")
-                .help("SWC neuron morphology file to use as input")
-                .index(1)
-                .required(true),
+            Arg::with_name("format")
+                .long("format")
+                .help(
+                    "Output format. GraphML has the kind, position, radius and parent of \
+                     each compartment as typed attributes. GEXF is laid out and styled \
+                     for Gephi. JSON is in the node-link format of NetworkX and D3. \
+                     Edgelist has a row per parent-child pair and nodes a row per \
+                     compartment with its metrics. SWC has the compartments of the \
+                     graph after any changes, such as --collapse. Inferred from the \
+                     extension of --output if not given [default: dot]",
+                )
+                .value_name("FORMAT")
+                .possible_values(&[
+                    "dot", "graphml", "gexf", "json", "gml", "pajek", "edgelist", "nodes", "swc",
+                ])
+                .takes_value(true),
         )
+        .arg(
+            Arg::with_name("delimiter")
+                .long("delimiter")
+                .help(
+                    "Column delimiter for the edgelist and nodes formats. Use `tab` or \
+                     `space` for whitespace [default: `tab` if --output ends in .tsv, \
+                     `,` otherwise]",
+                )
+                .value_name("DELIM")
+                .takes_value(true)
+                .validator(|value| validate_delimiter(&value)),
+        )
+        .arg(stdout_arg())
+        .arg(output_dir_arg())
+        .arg(jobs_arg())
+        .arg(input_arg())
         .arg(
             Arg::with_name("config")
                 .short("c")
@@ -27,17 +66,509 @@ pub fn get_cli_arguments<'a>() -> ArgMatches<'a> {
                 .value_name("FILE")
                 .takes_value(true),
         )
-        .get_matches()
+        .arg(lenient_arg())
+        .arg(
+            Arg::with_name("pos")
+                .long("pos")
+                .help(
+                    "Pin vertices to their anatomical positions (for `neato -n`) by \
+                     projecting onto the `xy`, `xz` or `yz` plane, or through a custom \
+                     2x3 view matrix given as six comma-separated numbers",
+                )
+                .value_name("PROJECTION")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .validator(|value| match value.parse::<Projection>() {
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(err.to_string()),
+                }),
+        )
+        .arg(
+            Arg::with_name("pos-scale")
+                .long("pos-scale")
+                .help("Points per SWC distance unit for `--pos` [default: 1]")
+                .value_name("SCALE")
+                .takes_value(true)
+                .requires("pos")
+                .validator(|value| match value.parse::<f64>() {
+                    Ok(scale) if scale.is_finite() && scale > 0.0 => Ok(()),
+                    _ => Err(format!("expected a positive number, got `{}`", value)),
+                }),
+        )
+        .args(&radius_scale_args())
+        .args(&color_args())
+        .arg(
+            Arg::with_name("directed")
+                .long("directed")
+                .help(
+                    "Write a directed graph (digraph) with edges pointing from parent to \
+                     child (default) or from child to parent",
+                )
+                .value_name("DIRECTION")
+                .possible_values(&["parent-to-child", "child-to-parent"])
+                .min_values(0)
+                .max_values(1)
+                .require_equals(true),
+        )
+        .arg(
+            Arg::with_name("vertex-metrics")
+                .long("vertex-metrics")
+                .help(
+                    "Add the centrifugal branch order and Strahler order of each vertex \
+                     to it as `branch_order` and `strahler_order`",
+                ),
+        )
+        .arg(
+            Arg::with_name("edge-metrics")
+                .long("edge-metrics")
+                .help(
+                    "Add the length, mean radius, surface area and volume of each segment \
+                     to its edge as `length`, `mean_radius`, `surface_area` and `volume`",
+                ),
+        )
+        .arg(
+            Arg::with_name("edge-len")
+                .long("edge-len")
+                .help(
+                    "Use segment length as the Graphviz `len` of each edge for neato \
+                     layouts, at SCALE points per SWC distance unit (default 1)",
+                )
+                .value_name("SCALE")
+                .min_values(0)
+                .max_values(1)
+                .require_equals(true)
+                .validator(|value| match value.parse::<f64>() {
+                    Ok(scale) if scale.is_finite() && scale > 0.0 => Ok(()),
+                    _ => Err(format!("expected a positive number, got `{}`", value)),
+                }),
+        )
+        .arg(
+            Arg::with_name("sholl-shells")
+                .long("sholl-shells")
+                .help(
+                    "Add the index of the Sholl shell around the soma that each vertex \
+                     lies in as `sholl_shell`",
+                ),
+        )
+        .arg(
+            Arg::with_name("sholl-step")
+                .long("sholl-step")
+                .help(
+                    "Distance between Sholl spheres in SWC units for `--sholl-shells` and \
+                     `--color-by sholl-shell` [default: 10]",
+                )
+                .value_name("STEP")
+                .takes_value(true)
+                .validator(|value| match value.parse::<f64>() {
+                    Ok(step) if step.is_finite() && step > 0.0 => Ok(()),
+                    _ => Err(format!("expected a positive number, got `{}`", value)),
+                }),
+        )
+        .arg(
+            Arg::with_name("collapse")
+                .long("collapse")
+                .help(
+                    "Keep only roots, branch points and terminals, joining them by edges \
+                     with the `length`, `compartments`, `mean_radius`, `surface_area` and \
+                     `volume` of the unbranched chain between them",
+                ),
+        )
+        .args(&swc_format_args())
+        .subcommand(
+            SubCommand::with_name("standardize")
+                .about(
+                    "Renumber compartments contiguously from 1 in depth-first order \
+                     and write the result as SWC.",
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("Output file for morphology in SWC format. Use `-` for stdout")
+                        .value_name("FILE")
+                        .takes_value(true),
+                )
+                .arg(stdout_arg())
+                .arg(output_dir_arg())
+                .arg(jobs_arg())
+                .arg(input_arg())
+                .arg(lenient_arg())
+                .args(&swc_format_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about(
+                    "Report total length, branch points, terminals, surface area, volume \
+                     and other measurements of each neuron.",
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("Output file for measurements [default: stdout]")
+                        .value_name("FILE")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .help(
+                            "Output format. JSON has an object per file and CSV has a row \
+                             per file [default: text]",
+                        )
+                        .value_name("FORMAT")
+                        .possible_values(&["text", "json", "csv"])
+                        .takes_value(true),
+                )
+                .arg(jobs_arg())
+                .arg(input_arg())
+                .arg(lenient_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("sholl")
+                .about(
+                    "Count how many times the neurites cross concentric spheres around \
+                     the soma (Sholl analysis).",
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("Output file for Sholl profiles [default: stdout]")
+                        .value_name("FILE")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .help(
+                            "Output format. CSV has a row per sphere and JSON has an \
+                             object per file [default: csv]",
+                        )
+                        .value_name("FORMAT")
+                        .possible_values(&["csv", "json"])
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("step")
+                        .long("step")
+                        .help("Distance between spheres in SWC units [default: 10]")
+                        .value_name("STEP")
+                        .takes_value(true)
+                        .validator(|value| match value.parse::<f64>() {
+                            Ok(step) if step.is_finite() && step > 0.0 => Ok(()),
+                            _ => Err(format!("expected a positive number, got `{}`", value)),
+                        }),
+                )
+                .arg(
+                    Arg::with_name("by-kind")
+                        .long("by-kind")
+                        .help("Also count intersections of each kind of neurite separately"),
+                )
+                .arg(jobs_arg())
+                .arg(input_arg())
+                .arg(lenient_arg()),
+        )
+}
+
+fn input_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("INPUT")
+        .help(
+            "SWC neuron morphology files to use as input. Directories are searched \
+             recursively for SWC files and glob patterns (eg `cells/*.swc`) are \
+             expanded. Use `-` for stdin",
+        )
+        .index(1)
+        .multiple(true)
+        .required(true)
+}
+
+fn output_dir_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("output-dir")
+        .long("output-dir")
+        .help(
+            "Directory for output files. The layout of directories given as INPUT \
+             is mirrored inside it",
+        )
+        .value_name("DIR")
+        .takes_value(true)
+        .conflicts_with_all(&["output", "stdout"])
+}
+
+fn stdout_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("stdout")
+        .long("stdout")
+        .help("Write output to stdout (same as `--output -`)")
+        .conflicts_with("output")
+}
+
+fn jobs_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("jobs")
+        .short("j")
+        .long("jobs")
+        .help("Number of files to convert in parallel. Use 0 for one per CPU core [default: 1]")
+        .value_name("N")
+        .takes_value(true)
+        .validator(|value| match value.parse::<usize>() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(format!("expected a non-negative integer, got `{}`", value)),
+        })
+}
+
+fn lenient_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("lenient")
+        .long("lenient")
+        .help(
+            "Report every problem in INPUT instead of stopping at the first one. \
+             Lines with problems are skipped, or repaired where possible \
+             (default).",
+        )
+        .value_name("POLICY")
+        .possible_values(&["skip", "repair"])
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
 }
 
-/// Get a filename with the extension removed.
+/// Options for sizing vertices and edges by compartment radius.
+fn radius_scale_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("radius-scale")
+            .long("radius-scale")
+            .help(
+                "Set vertex width/height and edge penwidth from compartment radius, \
+                 proportional to radius (`linear`), to log(1 + radius) (`log`), or \
+                 proportional to radius within `--radius-range` (`clamped`)",
+            )
+            .value_name("SCALE")
+            .takes_value(true)
+            .validator(|value| match value.parse::<RadiusMapping>() {
+                Ok(_) => Ok(()),
+                Err(err) => Err(err.to_string()),
+            }),
+        Arg::with_name("radius-factor")
+            .long("radius-factor")
+            .help("Points per SWC distance unit for `--radius-scale` [default: 1]")
+            .value_name("FACTOR")
+            .takes_value(true)
+            .requires("radius-scale")
+            .validator(|value| match value.parse::<f64>() {
+                Ok(factor) if factor.is_finite() && factor > 0.0 => Ok(()),
+                _ => Err(format!("expected a positive number, got `{}`", value)),
+            }),
+        Arg::with_name("radius-range")
+            .long("radius-range")
+            .help(
+                "Smallest and largest size in points for `--radius-scale clamped` \
+                 [default: 0.5,10]",
+            )
+            .value_name("MIN,MAX")
+            .takes_value(true)
+            .requires("radius-scale")
+            .validator(|value| match parse_range(&value) {
+                Ok((min, _)) if min < 0.0 => {
+                    Err(format!("sizes can't be negative, got `{}`", value))
+                }
+                Ok(_) => Ok(()),
+                Err(err) => Err(err),
+            }),
+    ]
+}
+
+/// Options for coloring vertices by a morphometric.
+fn color_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("color-by")
+            .long("color-by")
+            .help(
+                "Fill vertices with a color given by path distance from the soma \
+                 (`path-distance`), centrifugal branch order (`branch-order`), Strahler \
+                 number (`strahler`), compartment radius (`radius`), position along \
+                 an axis (`x`, `y` or `z`), or Sholl shell (`sholl-shell`)",
+            )
+            .value_name("METRIC")
+            .takes_value(true)
+            .validator(|value| match value.parse::<VertexMetric>() {
+                Ok(_) => Ok(()),
+                Err(err) => Err(err),
+            }),
+        Arg::with_name("colormap")
+            .long("colormap")
+            .help(
+                "Colormap for `--color-by`: `viridis`, `magma`, `plasma`, `inferno`, \
+                 `coolwarm` or `greys` [default: viridis]",
+            )
+            .value_name("NAME")
+            .takes_value(true)
+            .requires("color-by")
+            .validator(|value| match value.parse::<Colormap>() {
+                Ok(_) => Ok(()),
+                Err(err) => Err(err.to_string()),
+            }),
+        Arg::with_name("color-range")
+            .long("color-range")
+            .help(
+                "Values of `--color-by` at the two ends of the colormap. Values outside \
+                 the range get the color at the nearest end [default: smallest and \
+                 largest value]",
+            )
+            .value_name("MIN,MAX")
+            .takes_value(true)
+            .allow_hyphen_values(true)
+            .requires("color-by")
+            .validator(|value| parse_range(&value).map(|_| ())),
+    ]
+}
+
+/// Options that control how SWC output is formatted.
+fn swc_format_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("precision")
+            .long("precision")
+            .help("Number of digits after the decimal point for positions and radii")
+            .value_name("DIGITS")
+            .takes_value(true)
+            .validator(|value| match value.parse::<usize>() {
+                Ok(_) => Ok(()),
+                Err(_) => Err(format!("expected a non-negative integer, got `{}`", value)),
+            }),
+        Arg::with_name("separator")
+            .long("separator")
+            .help(
+                "Column separator of spaces and tabs. Use `tab` or `space` for a single one \
+                 [default: space]",
+            )
+            .value_name("SEP")
+            .takes_value(true)
+            .validator(|value| validate_separator(&value)),
+    ]
+}
+
+#[cfg(test)]
+mod app_tests {
+    use super::*;
+
+    #[test]
+    fn projection_can_start_with_a_negative_number() {
+        let matches = get_app()
+            .get_matches_from_safe(vec!["swc2dot", "--pos", "-1,0,0,0,1,0", "cell.swc"])
+            .expect("Could not parse arguments.");
+        assert_eq!(matches.value_of("pos"), Some("-1,0,0,0,1,0"));
+        assert_eq!(matches.value_of("INPUT"), Some("cell.swc"));
+    }
+}
+
+/// Get the column separator given on the command line.
 ///
-/// If the file does not have an extension, the whole filename is returned.
-pub fn get_filename_without_extension(filename: String) -> String {
-    let extension_start_position: usize;
-    match filename.rfind('.') {
-        Some(position) => extension_start_position = position,
-        None => extension_start_position = filename.len(),
-    }
-    return filename[0..extension_start_position].to_string();
+/// The names `tab` and `space` stand for the corresponding characters.
+pub fn get_separator(value: &str) -> String {
+    match value {
+        "tab" => "\t".to_string(),
+        "space" => " ".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Check that a column separator for SWC output can be read back, which
+/// means it consists of spaces and tabs.
+fn validate_separator(value: &str) -> Result<(), String> {
+    let separator = get_separator(value);
+    if separator.is_empty() || !separator.chars().all(|c| c == ' ' || c == '\t') {
+        return Err(format!(
+            "expected spaces and tabs, `tab` or `space`, got `{}`",
+            value
+        ));
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod validate_separator_tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_separators_are_valid() {
+        assert!(validate_separator("tab").is_ok());
+        assert!(validate_separator("space").is_ok());
+        assert!(validate_separator(" \t").is_ok());
+    }
+
+    #[test]
+    fn other_separators_are_invalid() {
+        assert!(validate_separator(",").is_err());
+        assert!(validate_separator("").is_err());
+        assert!(validate_separator(" ;").is_err());
+    }
+}
+
+/// Check that a column delimiter for tables can't be confused with the rest
+/// of the table.
+///
+/// Fields that contain the delimiter are quoted, so the delimiter can be
+/// anything but double quotes or line breaks.
+fn validate_delimiter(value: &str) -> Result<(), String> {
+    let delimiter = get_separator(value);
+    if delimiter.is_empty() {
+        return Err("the delimiter can't be empty".to_string());
+    }
+    if delimiter.contains(['"', '\n', '\r']) {
+        return Err(format!(
+            "the delimiter can't contain quotes or line breaks, got `{}`",
+            value
+        ));
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod validate_delimiter_tests {
+    use super::*;
+
+    #[test]
+    fn common_delimiters_are_valid() {
+        for delimiter in [",", ";", "|", "tab", "space", ".", "1"].iter() {
+            assert!(validate_delimiter(delimiter).is_ok());
+        }
+    }
+
+    #[test]
+    fn empty_delimiters_quotes_and_line_breaks_are_invalid() {
+        for delimiter in ["", "\"", "\n", "\r\n", "; \""].iter() {
+            assert!(validate_delimiter(delimiter).is_err());
+        }
+    }
+}
+
+/// Parse a range of numbers given as `MIN,MAX`.
+pub fn parse_range(value: &str) -> Result<(f64, f64), String> {
+    let bounds: Vec<&str> = value.split(',').collect();
+    let error = format!("expected `MIN,MAX`, got `{}`", value);
+    if bounds.len() != 2 {
+        return Err(error);
+    }
+    match (
+        bounds[0].trim().parse::<f64>(),
+        bounds[1].trim().parse::<f64>(),
+    ) {
+        (Ok(min), Ok(max)) if min.is_finite() && max.is_finite() && min <= max => Ok((min, max)),
+        _ => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod parse_range_tests {
+    use super::*;
+
+    #[test]
+    fn valid_range() {
+        assert_eq!(parse_range("0.5, 12"), Ok((0.5, 12.0)));
+        assert_eq!(parse_range("-3,-1"), Ok((-3.0, -1.0)));
+    }
+
+    #[test]
+    fn invalid_range() {
+        assert!(parse_range("3,1").is_err());
+        assert!(parse_range("1").is_err());
+        assert!(parse_range("a,b").is_err());
+    }
 }
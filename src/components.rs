@@ -1,12 +1,134 @@
-use std::collections::{btree_map::Iter, BTreeMap};
+#![allow(
+    clippy::needless_late_init,
+    clippy::needless_return,
+    clippy::redundant_field_names,
+    clippy::redundant_static_lifetimes
+)]
+
+use std::collections::{btree_map::Iter, BTreeMap, BTreeSet};
 use std::convert::From;
 
-use crate::swc_parser::{SWCCompartment, SWCCompartmentKind, SWCNeuron};
+use linked_hash_map::LinkedHashMap;
+
+use crate::analysis::Segment;
+use crate::swc_parser::{Metadata, Point, SWCCompartment, SWCCompartmentKind, SWCNeuron};
+use crate::writer::format_number;
+
+/// Key/value attributes of a single vertex or edge, in insertion order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Attributes {
+    entries: LinkedHashMap<String, String>,
+    /// Keys of attributes that were set to numbers.
+    numbers: BTreeSet<String>,
+}
+
+impl Attributes {
+    pub fn new() -> Attributes {
+        Attributes {
+            entries: LinkedHashMap::new(),
+            numbers: BTreeSet::new(),
+        }
+    }
+
+    /// Set an attribute, replacing any existing value for the same key.
+    pub fn insert(&mut self, key: &str, value: String) {
+        self.entries.insert(key.to_string(), value);
+        self.numbers.remove(key);
+    }
+
+    /// Set an attribute to a number rounded to 4 decimal places, replacing
+    /// any existing value for the same key.
+    ///
+    /// Formats with typed attributes write it as a number (see `is_number()`).
+    pub fn insert_number(&mut self, key: &str, value: f64) {
+        self.entries.insert(key.to_string(), format_number(value, 4));
+        self.numbers.insert(key.to_string());
+    }
+
+    /// Remove an attribute, if it is set.
+    pub fn remove(&mut self, key: &str) {
+        self.entries.remove(key);
+        self.numbers.remove(key);
+    }
+
+    /// Set all attributes in `other`, replacing existing values for the same keys.
+    pub fn extend(&mut self, other: &Attributes) {
+        for (key, value) in other.iter() {
+            self.entries.insert(key.clone(), value.clone());
+            if other.is_number(key) {
+                self.numbers.insert(key.clone());
+            } else {
+                self.numbers.remove(key);
+            }
+        }
+    }
+
+    /// Check whether an attribute was set to a number with `insert_number()`.
+    pub fn is_number(&self, key: &str) -> bool {
+        self.numbers.contains(key)
+    }
+
+    pub fn iter(&self) -> linked_hash_map::Iter<'_, String, String> {
+        self.entries.iter()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.entries.get(key)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod attributes_tests {
+    use super::*;
+
+    #[test]
+    fn insertion_order_is_kept() {
+        let mut attributes = Attributes::new();
+        attributes.insert("b", "1".to_string());
+        attributes.insert("a", "2".to_string());
+        let keys: Vec<&String> = attributes.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn extend_replaces_existing_values() {
+        let mut attributes = Attributes::new();
+        attributes.insert("color", "red".to_string());
+        attributes.insert("label", "a".to_string());
+        let mut overrides = Attributes::new();
+        overrides.insert("color", "blue".to_string());
+        attributes.extend(&overrides);
+        assert_eq!(attributes.get("color"), Some(&"blue".to_string()));
+        assert_eq!(attributes.get("label"), Some(&"a".to_string()));
+    }
+
+    #[test]
+    fn numbers_keep_their_type() {
+        let mut attributes = Attributes::new();
+        attributes.insert_number("length", 2.0 / 3.0);
+        attributes.insert("label", "007".to_string());
+        assert_eq!(attributes.get("length"), Some(&"0.6667".to_string()));
+        assert!(attributes.is_number("length"));
+        assert!(!attributes.is_number("label"));
+
+        let mut overrides = Attributes::new();
+        overrides.insert("length", "long".to_string());
+        overrides.insert_number("label", 7.0);
+        attributes.extend(&overrides);
+        assert!(!attributes.is_number("length"));
+        assert!(attributes.is_number("label"));
+    }
+}
 
 #[derive(Clone)]
 pub struct Vertex {
     data: SWCCompartment,
     children: Vec<usize>,
+    attributes: Attributes,
 }
 
 impl Vertex {
@@ -26,8 +148,25 @@ impl Vertex {
         self.data.kind
     }
 
-    fn add_child(&mut self, child: &Vertex) {
-        self.children.push(child.get_id());
+    pub fn get_position(&self) -> Point {
+        self.data.position
+    }
+
+    pub fn get_radius(&self) -> f64 {
+        self.data.radius
+    }
+
+    pub fn get_compartment(&self) -> &SWCCompartment {
+        &self.data
+    }
+
+    /// Get attributes set on this vertex in particular.
+    pub fn get_attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    fn add_child(&mut self, child_id: usize) {
+        self.children.push(child_id);
     }
 }
 
@@ -36,6 +175,7 @@ impl From<SWCCompartment> for Vertex {
         Vertex {
             data: compartment,
             children: Vec::<usize>::with_capacity(4),
+            attributes: Attributes::new(),
         }
     }
 }
@@ -68,7 +208,6 @@ mod vertex_from_swccompartment_tests {
     }
 
     fn get_test_swccompartment(id: usize, parent_id: Option<usize>) -> SWCCompartment {
-        use crate::swc_parser::Point;
         SWCCompartment::new(
             id,
             SWCCompartmentKind::Soma,
@@ -79,61 +218,805 @@ mod vertex_from_swccompartment_tests {
     }
 }
 
+/// Measurements of an unbranched chain of segments that was collapsed into a
+/// single edge (see `Graph::collapse()`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Chain {
+    /// Total length, surface area and volume of the segments of the chain,
+    /// and their mean radius weighted by length.
+    pub segment: Segment,
+    /// Number of compartments in the chain, counting the child but not the
+    /// parent.
+    pub compartments: usize,
+}
+
+/// Names of the attributes that collapsed edges get from their chain (see
+/// `writer::get_edge_attributes_with_chain()`).
+static CHAIN_MEASUREMENTS: &'static [&'static str] = &[
+    "length",
+    "compartments",
+    "mean_radius",
+    "surface_area",
+    "volume",
+];
+
 pub struct Graph {
     vertices: BTreeMap<usize, Vertex>,
+    /// Attributes of edges, keyed by `(parent_id, child_id)`.
+    edge_attributes: BTreeMap<(usize, usize), Attributes>,
+    /// Chains that collapsed edges stand for, keyed by `(parent_id, child_id)`.
+    chains: BTreeMap<(usize, usize), Chain>,
+    comments: Vec<String>,
+    metadata: Metadata,
 }
 
 impl Graph {
-    pub fn iter_vertices(&self) -> Iter<usize, Vertex> {
+    pub fn iter_vertices(&self) -> Iter<'_, usize, Vertex> {
         self.vertices.iter()
     }
 
+    /// Iterate over `(parent_id, child_id)` pairs of every edge.
+    pub fn iter_edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.vertices.values().flat_map(|vertex| {
+            vertex
+                .get_child_ids()
+                .iter()
+                .map(move |child_id| (vertex.get_id(), *child_id))
+        })
+    }
+
     pub fn iter_short_trees(&self) -> ShortTreeIter {
         let mut short_trees = Vec::with_capacity(self.vertices.len());
-        for (id, vertex) in self.iter_vertices() {
+        for (_, vertex) in self.iter_vertices() {
             short_trees.push(ShortTree::from(vertex.clone()));
         }
         ShortTreeIter::new(short_trees)
     }
 
+    pub fn get_vertex(&self, id: usize) -> Option<&Vertex> {
+        self.vertices.get(&id)
+    }
+
     pub fn len(&self) -> usize {
         self.vertices.len()
     }
-}
 
-impl From<SWCNeuron> for Graph {
-    fn from(neuron: SWCNeuron) -> Graph {
-        let mut graph = Graph {
-            vertices: BTreeMap::<usize, Vertex>::new(),
-        };
+    /// Set an attribute of a single vertex.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no vertex with the given id.
+    pub fn set_vertex_attribute(&mut self, id: usize, key: &str, value: String) {
+        self.get_vertex_attributes_mut(id).insert(key, value);
+    }
+
+    /// Set an attribute of a single vertex to a number (see
+    /// `Attributes::insert_number()`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no vertex with the given id.
+    pub fn set_vertex_number(&mut self, id: usize, key: &str, value: f64) {
+        self.get_vertex_attributes_mut(id).insert_number(key, value);
+    }
+
+    #[allow(clippy::expect_fun_call)]
+    fn get_vertex_attributes_mut(&mut self, id: usize) -> &mut Attributes {
+        &mut self
+            .vertices
+            .get_mut(&id)
+            .expect(&format!("Vertex {} does not exist", id))
+            .attributes
+    }
+
+    /// Get attributes set on the edge between a vertex and one of its children.
+    pub fn get_edge_attributes(&self, parent_id: usize, child_id: usize) -> Option<&Attributes> {
+        self.edge_attributes.get(&(parent_id, child_id))
+    }
+
+    /// Set an attribute of the edge between a vertex and one of its children.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `child_id` is not a child of `parent_id`.
+    #[cfg(test)]
+    pub fn set_edge_attribute(
+        &mut self,
+        parent_id: usize,
+        child_id: usize,
+        key: &str,
+        value: String,
+    ) {
+        self.get_edge_attributes_mut(parent_id, child_id)
+            .insert(key, value);
+    }
+
+    /// Set an attribute of the edge between a vertex and one of its children
+    /// to a number (see `Attributes::insert_number()`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `child_id` is not a child of `parent_id`.
+    pub fn set_edge_number(&mut self, parent_id: usize, child_id: usize, key: &str, value: f64) {
+        self.get_edge_attributes_mut(parent_id, child_id)
+            .insert_number(key, value);
+    }
+
+    fn get_edge_attributes_mut(&mut self, parent_id: usize, child_id: usize) -> &mut Attributes {
+        assert!(
+            self.vertices
+                .get(&child_id)
+                .is_some_and(|child| child.get_parent_id() == Some(parent_id)),
+            "There is no edge from vertex {} to vertex {}",
+            parent_id,
+            child_id
+        );
+        self.edge_attributes
+            .entry((parent_id, child_id))
+            .or_default()
+    }
+
+    /// Get the chain of segments that the edge between a vertex and one of
+    /// its children stands for, if the graph was collapsed.
+    pub fn get_chain(&self, parent_id: usize, child_id: usize) -> Option<&Chain> {
+        self.chains.get(&(parent_id, child_id))
+    }
+
+    /// Get the geometry of the edge between a vertex and one of its children.
+    ///
+    /// This is the segment between the two vertices or, for edges of
+    /// collapsed graphs, the whole chain of segments the edge stands for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either vertex does not exist.
+    pub fn get_segment(&self, parent_id: usize, child_id: usize) -> Segment {
+        match self.get_chain(parent_id, child_id) {
+            Some(chain) => chain.segment,
+            None => Segment::between(&self.vertices[&parent_id], &self.vertices[&child_id]),
+        }
+    }
+
+    /// Get the comment lines of the SWC file the graph was built from.
+    pub fn get_comments(&self) -> &Vec<String> {
+        &self.comments
+    }
+
+    /// Get the well-known metadata of the SWC file the graph was built from.
+    pub fn get_metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Get the ids of all vertices without a parent, in ascending order.
+    pub fn get_root_ids(&self) -> Vec<usize> {
+        self.vertices
+            .values()
+            .filter(|vertex| vertex.get_parent_id().is_none())
+            .map(|vertex| vertex.get_id())
+            .collect()
+    }
+
+    /// Get the ids of all vertices in depth-first order from each root, so
+    /// parents always come before their children.
+    pub fn get_depth_first_ids(&self) -> Vec<usize> {
+        // Use an explicit stack since unbranched chains can be very long.
+        let mut visit_order = Vec::with_capacity(self.len());
+        let mut stack = self.get_root_ids();
+        stack.reverse();
+        while let Some(id) = stack.pop() {
+            visit_order.push(id);
+            stack.extend(self.vertices[&id].get_child_ids().iter().rev());
+        }
+        return visit_order;
+    }
+
+    /// Get the centrifugal branch order of each vertex.
+    ///
+    /// Somatic vertices have order 0, neurites leaving the soma start at
+    /// order 1, and the order goes up by one after every branch point. A
+    /// neurite without a soma starts at order 1 at its root.
+    pub fn get_branch_orders(&self) -> BTreeMap<usize, usize> {
+        let mut orders = BTreeMap::new();
+        for id in self.get_depth_first_ids() {
+            let vertex = &self.vertices[&id];
+            let order = if vertex.get_kind() == SWCCompartmentKind::Soma {
+                0
+            } else {
+                match vertex.get_parent_id() {
+                    Some(parent_id) => {
+                        let parent = &self.vertices[&parent_id];
+                        let is_branch_point = parent.get_child_ids().len() > 1
+                            || parent.get_kind() == SWCCompartmentKind::Soma;
+                        orders[&parent_id] + if is_branch_point { 1 } else { 0 }
+                    }
+                    None => 1,
+                }
+            };
+            orders.insert(id, order);
+        }
+        return orders;
+    }
+
+    /// Get the Strahler order of each vertex.
+    ///
+    /// Tips have order 1. A vertex takes the largest order of its children,
+    /// plus one if two or more children share that order.
+    pub fn get_strahler_orders(&self) -> BTreeMap<usize, usize> {
+        let mut orders = BTreeMap::new();
+        // Visit children before their parents.
+        for id in self.get_depth_first_ids().into_iter().rev() {
+            let child_orders: Vec<usize> = self.vertices[&id]
+                .get_child_ids()
+                .iter()
+                .map(|child_id| orders[child_id])
+                .collect();
+            let order = match child_orders.iter().max() {
+                Some(max) if child_orders.iter().filter(|o| *o == max).count() > 1 => max + 1,
+                Some(max) => *max,
+                None => 1,
+            };
+            orders.insert(id, order);
+        }
+        return orders;
+    }
 
-        for (_, compartment) in neuron.iter() {
-            let vertex = Vertex::from(compartment.clone());
-
-            match vertex.get_parent_id() {
-                Some(parent_id) => {
-                    // Preconditions that should be guaranteed by the parser:
-                    // 1. The ID of the parent must be less than the ID of the child to comply with
-                    //    SWC standard
-                    // 2. It is invalid for a child to have a parent that does not exist.
-                    debug_assert!(parent_id < vertex.get_id());
-                    debug_assert!(graph.vertices.contains_key(&parent_id));
-
-                    // Add vertex as a child of its parent.
-                    let parent = graph.vertices.get_mut(&parent_id).unwrap();
-                    parent.add_child(&vertex);
+    /// Get a copy of the graph with unbranched chains collapsed into single
+    /// edges.
+    ///
+    /// Only roots, branch points and terminals are kept, with their original
+    /// ids and attributes. Each kept vertex is joined to its nearest kept
+    /// ancestor by an edge that stands for the chain of segments between them,
+    /// whose measurements are kept for the edge (see `get_chain()`). The edge
+    /// keeps the attributes that every edge of the chain has with the same
+    /// value, except for those named after a chain measurement, which would
+    /// describe a single segment.
+    pub fn collapse(&self) -> Graph {
+        let is_kept =
+            |vertex: &Vertex| vertex.get_parent_id().is_none() || vertex.get_child_ids().len() != 1;
+
+        let mut compartments = Vec::new();
+        let mut chains = Vec::new();
+        for vertex in self.vertices.values().filter(|vertex| is_kept(vertex)) {
+            let mut compartment = vertex.data;
+            if let Some(mut parent_id) = vertex.get_parent_id() {
+                let mut child = vertex;
+                let mut segments = Vec::new();
+                loop {
+                    let parent = &self.vertices[&parent_id];
+                    segments.push(Segment::between(parent, child));
+                    if is_kept(parent) {
+                        break;
+                    }
+                    child = parent;
+                    parent_id = parent.get_parent_id().unwrap();
                 }
-                None => {}
+                compartment.parent_id = Some(parent_id);
+                chains.push((parent_id, vertex.get_id(), segments));
+            }
+            compartments.push(compartment);
+        }
+
+        let mut graph = Graph::from_compartments(compartments);
+        for (id, vertex) in graph.vertices.iter_mut() {
+            vertex.attributes = self.vertices[id].attributes.clone();
+        }
+        for (parent_id, child_id, segments) in chains {
+            let attributes = self.get_chain_attributes(parent_id, child_id);
+            if !attributes.is_empty() {
+                graph
+                    .edge_attributes
+                    .insert((parent_id, child_id), attributes);
+            }
+            let length: f64 = segments.iter().map(|segment| segment.length).sum();
+            // Fall back on a plain mean for chains of zero length.
+            let mean_radius = if length > 0.0 {
+                segments
+                    .iter()
+                    .map(|segment| segment.mean_radius * segment.length)
+                    .sum::<f64>()
+                    / length
+            } else {
+                segments.iter().map(|segment| segment.mean_radius).sum::<f64>()
+                    / segments.len() as f64
+            };
+            let chain = Chain {
+                segment: Segment {
+                    length: length,
+                    mean_radius: mean_radius,
+                    surface_area: segments.iter().map(|segment| segment.surface_area).sum(),
+                    volume: segments.iter().map(|segment| segment.volume).sum(),
+                },
+                compartments: segments.len(),
+            };
+            graph.chains.insert((parent_id, child_id), chain);
+        }
+        graph.comments = self.comments.clone();
+        graph.metadata = self.metadata.clone();
+        return graph;
+    }
+
+    /// Get the attributes that every edge of the chain from `ancestor_id` down
+    /// to `id` has with the same value, except for chain measurements.
+    fn get_chain_attributes(&self, ancestor_id: usize, id: usize) -> Attributes {
+        let mut edges = Vec::new();
+        let mut child_id = id;
+        while child_id != ancestor_id {
+            let parent_id = self.vertices[&child_id].get_parent_id().unwrap();
+            edges.push(self.get_edge_attributes(parent_id, child_id));
+            child_id = parent_id;
+        }
+
+        let mut attributes = match edges.first() {
+            Some(Some(first)) => (*first).clone(),
+            _ => return Attributes::new(),
+        };
+        let unshared: Vec<String> = attributes
+            .iter()
+            .filter(|(key, value)| {
+                CHAIN_MEASUREMENTS.contains(&key.as_str())
+                    || !edges.iter().all(|edge| {
+                        edge.is_some_and(|edge| {
+                            edge.get(key) == Some(value)
+                                && edge.is_number(key) == attributes.is_number(key)
+                        })
+                    })
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in unshared {
+            attributes.remove(&key);
+        }
+        return attributes;
+    }
+
+    /// Get a copy of the graph in standard form.
+    ///
+    /// In standard form,
+    ///
+    /// 1. there is exactly one root, preferably a somatic vertex,
+    /// 2. vertices are numbered contiguously from 1 in depth-first order, so
+    ///    parents always come before their children, and
+    /// 3. siblings are visited in the order of their original ids.
+    ///
+    /// Extra roots that duplicate the main root (same kind, position and
+    /// radius) are merged into it, and all other extra roots are attached to
+    /// the main root as children.
+    pub fn standardize(&self) -> Graph {
+        let root_ids = self.get_root_ids();
+        let main_root_id = match root_ids
+            .iter()
+            .find(|id| self.vertices[id].get_kind() == SWCCompartmentKind::Soma)
+            .or_else(|| root_ids.first())
+        {
+            Some(id) => *id,
+            None => return Graph::from_compartments(Vec::new()),
+        };
+        let main_root = &self.vertices[&main_root_id];
+
+        // Re-attach extra roots to the main root.
+        let mut parent_ids = BTreeMap::<usize, usize>::new();
+        let mut child_ids = BTreeMap::<usize, Vec<usize>>::new();
+        for (id, vertex) in self.iter_vertices() {
+            child_ids.insert(*id, vertex.get_child_ids().clone());
+            if let Some(parent_id) = vertex.get_parent_id() {
+                parent_ids.insert(*id, parent_id);
+            }
+        }
+        for root_id in root_ids.iter().filter(|id| **id != main_root_id) {
+            let root = &self.vertices[root_id];
+            let is_duplicate = root.get_kind() == main_root.get_kind()
+                && root.get_position() == main_root.get_position()
+                && root.get_radius() == main_root.get_radius();
+            let adopted_ids = if is_duplicate {
+                child_ids.remove(root_id).unwrap()
+            } else {
+                vec![*root_id]
+            };
+            for adopted_id in adopted_ids {
+                parent_ids.insert(adopted_id, main_root_id);
+                child_ids.get_mut(&main_root_id).unwrap().push(adopted_id);
             }
+        }
+        for children in child_ids.values_mut() {
+            children.sort_unstable();
+        }
+
+        // Number vertices in depth-first order, using an explicit stack since
+        // unbranched chains can be very long.
+        let mut new_ids = BTreeMap::<usize, usize>::new();
+        let mut visit_order = Vec::with_capacity(self.len());
+        let mut stack = vec![main_root_id];
+        while let Some(id) = stack.pop() {
+            visit_order.push(id);
+            new_ids.insert(id, visit_order.len());
+            stack.extend(child_ids[&id].iter().rev());
+        }
+        debug_assert!(visit_order.len() == child_ids.len());
+
+        let compartments = visit_order
+            .iter()
+            .map(|old_id| {
+                let mut compartment = self.vertices[old_id].data;
+                compartment.id = new_ids[old_id];
+                compartment.parent_id = parent_ids.get(old_id).map(|parent_id| new_ids[parent_id]);
+                compartment
+            })
+            .collect();
+        let mut graph = Graph::from_compartments(compartments);
+        graph.comments = self.comments.clone();
+        graph.metadata = self.metadata.clone();
+        return graph;
+    }
+
+    /// Build a graph from compartments whose parents all exist.
+    ///
+    /// Parents do not need to come before their children.
+    #[allow(clippy::expect_fun_call)]
+    fn from_compartments(compartments: Vec<SWCCompartment>) -> Graph {
+        let mut graph = Graph {
+            vertices: BTreeMap::<usize, Vertex>::new(),
+            edge_attributes: BTreeMap::new(),
+            chains: BTreeMap::new(),
+            comments: Vec::new(),
+            metadata: Metadata::new(),
+        };
 
+        // First pass: add every compartment as a vertex. Parents are not
+        // guaranteed to come before their children.
+        for compartment in compartments {
+            let vertex = Vertex::from(compartment);
             debug_assert!(!graph.vertices.contains_key(&vertex.get_id()));
             graph.vertices.insert(vertex.get_id(), vertex);
         }
 
+        // Second pass: add each vertex as a child of its parent.
+        let child_ids: Vec<(usize, usize)> = graph
+            .vertices
+            .values()
+            .filter_map(|vertex| {
+                vertex
+                    .get_parent_id()
+                    .map(|parent_id| (parent_id, vertex.get_id()))
+            })
+            .collect();
+        for (parent_id, child_id) in child_ids {
+            // Precondition that should be guaranteed by the parser: it is
+            // invalid for a child to have a parent that does not exist.
+            let parent = graph.vertices.get_mut(&parent_id).expect(&format!(
+                "Parent {} of vertex {} does not exist",
+                parent_id, child_id
+            ));
+            parent.add_child(child_id);
+        }
+
+        return graph;
+    }
+}
+
+impl From<SWCNeuron> for Graph {
+    fn from(neuron: SWCNeuron) -> Graph {
+        let mut graph =
+            Graph::from_compartments(neuron.iter().map(|(_, compartment)| *compartment).collect());
+        graph.comments = neuron.get_comments().clone();
+        graph.metadata = neuron.get_metadata().clone();
         return graph;
     }
 }
 
+#[cfg(test)]
+mod graph_attributes_tests {
+    use super::*;
+
+    fn get_test_graph() -> Graph {
+        let compartment = |id: usize, parent_id: Option<usize>| {
+            SWCCompartment::new(
+                id,
+                SWCCompartmentKind::Dendrite,
+                Point {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                1.0,
+                parent_id,
+            )
+        };
+        Graph::from_compartments(vec![compartment(1, None), compartment(2, Some(1))])
+    }
+
+    #[test]
+    fn vertex_attribute() {
+        let mut graph = get_test_graph();
+        graph.set_vertex_attribute(2, "label", "tip".to_string());
+        assert_eq!(
+            graph.get_vertex(2).unwrap().get_attributes().get("label"),
+            Some(&"tip".to_string())
+        );
+        assert!(graph.get_vertex(1).unwrap().get_attributes().is_empty());
+    }
+
+    #[test]
+    fn edge_attribute() {
+        let mut graph = get_test_graph();
+        graph.set_edge_attribute(1, 2, "color", "red".to_string());
+        assert_eq!(
+            graph.get_edge_attributes(1, 2).unwrap().get("color"),
+            Some(&"red".to_string())
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn attribute_of_missing_edge_panics() {
+        let mut graph = get_test_graph();
+        graph.set_edge_attribute(2, 1, "color", "red".to_string());
+    }
+}
+
+#[cfg(test)]
+mod graph_order_tests {
+    use super::*;
+
+    /// A soma with one dendrite that forks twice.
+    ///
+    /// ```text
+    /// 1 (soma) - 2 - 3 -+- 4
+    ///                   +- 5 -+- 6
+    ///                         +- 7
+    /// ```
+    fn get_test_graph() -> Graph {
+        let compartment = |id: usize, kind: SWCCompartmentKind, parent_id: Option<usize>| {
+            SWCCompartment::new(
+                id,
+                kind,
+                Point {
+                    x: 0.0,
+                    y: id as f64,
+                    z: 0.0,
+                },
+                1.0,
+                parent_id,
+            )
+        };
+        let dendrite = SWCCompartmentKind::Dendrite;
+        Graph::from_compartments(vec![
+            compartment(1, SWCCompartmentKind::Soma, None),
+            compartment(2, dendrite, Some(1)),
+            compartment(3, dendrite, Some(2)),
+            compartment(4, dendrite, Some(3)),
+            compartment(5, dendrite, Some(3)),
+            compartment(6, dendrite, Some(5)),
+            compartment(7, dendrite, Some(5)),
+        ])
+    }
+
+    fn to_map(orders: Vec<(usize, usize)>) -> BTreeMap<usize, usize> {
+        orders.into_iter().collect()
+    }
+
+    #[test]
+    fn parents_come_before_children() {
+        assert_eq!(
+            get_test_graph().get_depth_first_ids(),
+            vec![1, 2, 3, 4, 5, 6, 7]
+        );
+    }
+
+    #[test]
+    fn branch_orders() {
+        assert_eq!(
+            get_test_graph().get_branch_orders(),
+            to_map(vec![(1, 0), (2, 1), (3, 1), (4, 2), (5, 2), (6, 3), (7, 3)])
+        );
+    }
+
+    #[test]
+    fn strahler_orders() {
+        assert_eq!(
+            get_test_graph().get_strahler_orders(),
+            to_map(vec![(1, 2), (2, 2), (3, 2), (4, 1), (5, 2), (6, 1), (7, 1)])
+        );
+    }
+
+    #[test]
+    fn branch_orders_without_soma_start_at_one() {
+        let graph = Graph::from_compartments(vec![SWCCompartment::new(
+            1,
+            SWCCompartmentKind::Axon,
+            Point {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            1.0,
+            None,
+        )]);
+        assert_eq!(graph.get_branch_orders(), to_map(vec![(1, 1)]));
+        assert_eq!(graph.get_strahler_orders(), to_map(vec![(1, 1)]));
+    }
+}
+
+#[cfg(test)]
+mod graph_collapse_tests {
+    use super::*;
+
+    /// A soma with one dendrite that forks into a single segment and a chain.
+    ///
+    /// ```text
+    /// 1 (soma) - 2 - 3 -+- 4
+    ///                   +- 5 - 6
+    /// ```
+    fn get_test_graph() -> Graph {
+        let compartment = |id: usize, x: f64, y: f64, radius: f64, parent_id: Option<usize>| {
+            let kind = if parent_id.is_none() {
+                SWCCompartmentKind::Soma
+            } else {
+                SWCCompartmentKind::Dendrite
+            };
+            SWCCompartment::new(id, kind, Point { x: x, y: y, z: 0.0 }, radius, parent_id)
+        };
+        Graph::from_compartments(vec![
+            compartment(1, 0.0, 0.0, 3.0, None),
+            compartment(2, 0.0, 2.0, 1.0, Some(1)),
+            compartment(3, 0.0, 4.0, 1.0, Some(2)),
+            compartment(4, 1.0, 4.0, 1.0, Some(3)),
+            compartment(5, -1.0, 4.0, 1.0, Some(3)),
+            compartment(6, -3.0, 4.0, 1.0, Some(5)),
+        ])
+    }
+
+    #[test]
+    fn keeps_roots_branch_points_and_terminals() {
+        let collapsed = get_test_graph().collapse();
+        let ids: Vec<usize> = collapsed.iter_vertices().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![1, 3, 4, 6]);
+        let edges: Vec<(usize, usize)> = collapsed.iter_edges().collect();
+        assert_eq!(edges, vec![(1, 3), (3, 4), (3, 6)]);
+    }
+
+    #[allow(clippy::expect_fun_call)]
+    fn get_chain(graph: &Graph, parent_id: usize, child_id: usize) -> Chain {
+        *graph
+            .get_chain(parent_id, child_id)
+            .expect(&format!("Edge {}-{} is not a chain", parent_id, child_id))
+    }
+
+    #[test]
+    fn edges_keep_chain_measurements() {
+        let collapsed = get_test_graph().collapse();
+        let chain = get_chain(&collapsed, 1, 3);
+        assert_eq!(chain.segment.length, 4.0);
+        assert_eq!(chain.compartments, 2);
+        assert_eq!(chain.segment.mean_radius, 1.5);
+        assert_eq!(get_chain(&collapsed, 3, 4).segment.length, 1.0);
+        assert_eq!(get_chain(&collapsed, 3, 4).compartments, 1);
+        let chain = get_chain(&collapsed, 3, 6);
+        assert_eq!(chain.segment.length, 3.0);
+        assert!((chain.segment.volume - 3.0 * std::f64::consts::PI).abs() < 1e-12);
+        assert_eq!(collapsed.get_segment(3, 6), chain.segment);
+    }
+
+    #[test]
+    fn attributes_shared_by_whole_chain_are_kept() {
+        let mut graph = get_test_graph();
+        graph.set_edge_attribute(1, 2, "color", "red".to_string());
+        for (parent_id, child_id) in [(3, 5), (5, 6)].iter() {
+            graph.set_edge_attribute(*parent_id, *child_id, "color", "blue".to_string());
+            graph.set_edge_number(*parent_id, *child_id, "length", 1.5);
+        }
+        graph.set_edge_attribute(3, 5, "style", "dashed".to_string());
+        graph.set_edge_attribute(5, 6, "style", "bold".to_string());
+        graph.set_edge_attribute(5, 6, "label", "tip".to_string());
+        let collapsed = graph.collapse();
+
+        assert!(collapsed.get_edge_attributes(1, 3).is_none());
+        let mut expected = Attributes::new();
+        expected.insert("color", "blue".to_string());
+        assert_eq!(collapsed.get_edge_attributes(3, 6), Some(&expected));
+        assert_eq!(get_chain(&collapsed, 3, 6).segment.length, 3.0);
+    }
+
+    #[test]
+    fn vertex_attributes_are_kept() {
+        let mut graph = get_test_graph();
+        graph.set_vertex_attribute(3, "label", "fork".to_string());
+        let collapsed = graph.collapse();
+        assert_eq!(
+            collapsed
+                .get_vertex(3)
+                .unwrap()
+                .get_attributes()
+                .get("label"),
+            Some(&"fork".to_string())
+        );
+    }
+}
+
+#[cfg(test)]
+mod graph_standardize_tests {
+    use super::*;
+
+    fn compartment(
+        id: usize,
+        kind: SWCCompartmentKind,
+        x: f64,
+        parent_id: Option<usize>,
+    ) -> SWCCompartment {
+        SWCCompartment::new(id, kind, Point { x: x, y: 0.0, z: 0.0 }, 1.0, parent_id)
+    }
+
+    fn parent_ids(graph: &Graph) -> Vec<(usize, Option<usize>)> {
+        graph
+            .iter_vertices()
+            .map(|(id, vertex)| (*id, vertex.get_parent_id()))
+            .collect()
+    }
+
+    #[test]
+    fn renumbers_depth_first_from_one() {
+        use SWCCompartmentKind::*;
+        let graph = Graph::from_compartments(vec![
+            compartment(10, Soma, 0.0, None),
+            compartment(20, Dendrite, 1.0, Some(10)),
+            compartment(30, Dendrite, 2.0, Some(10)),
+            compartment(40, Dendrite, 3.0, Some(20)),
+        ]);
+        let standardized = graph.standardize();
+        assert_eq!(
+            parent_ids(&standardized),
+            vec![(1, None), (2, Some(1)), (3, Some(2)), (4, Some(1))]
+        );
+        assert_eq!(standardized.vertices[&3].get_position().x, 3.0);
+    }
+
+    #[test]
+    fn parents_come_before_children() {
+        use SWCCompartmentKind::*;
+        let graph = Graph::from_compartments(vec![
+            compartment(1, Dendrite, 1.0, Some(3)),
+            compartment(2, Dendrite, 2.0, Some(1)),
+            compartment(3, Soma, 0.0, None),
+        ]);
+        for (id, vertex) in graph.standardize().iter_vertices() {
+            if let Some(parent_id) = vertex.get_parent_id() {
+                assert!(parent_id < *id);
+            }
+        }
+    }
+
+    #[test]
+    fn prefers_somatic_root() {
+        use SWCCompartmentKind::*;
+        let graph = Graph::from_compartments(vec![
+            compartment(1, Axon, 5.0, None),
+            compartment(2, Soma, 0.0, None),
+        ]);
+        let standardized = graph.standardize();
+        assert_eq!(standardized.vertices[&1].get_kind(), Soma);
+        assert_eq!(parent_ids(&standardized), vec![(1, None), (2, Some(1))]);
+    }
+
+    #[test]
+    fn merges_duplicate_roots() {
+        use SWCCompartmentKind::*;
+        let graph = Graph::from_compartments(vec![
+            compartment(1, Soma, 0.0, None),
+            compartment(2, Dendrite, 1.0, Some(1)),
+            compartment(3, Soma, 0.0, None),
+            compartment(4, Dendrite, 2.0, Some(3)),
+        ]);
+        let standardized = graph.standardize();
+        assert_eq!(standardized.len(), 3);
+        assert_eq!(standardized.get_root_ids(), vec![1]);
+        assert_eq!(standardized.vertices[&1].get_child_ids(), &vec![2, 3]);
+    }
+
+    #[test]
+    fn empty_graph() {
+        assert_eq!(Graph::from_compartments(Vec::new()).standardize().len(), 0);
+    }
+}
+
 /// A tree of height 1.
 ///
 /// In DOT language, a tree of height 1 can be declared in one line.
@@ -144,6 +1027,13 @@ pub struct ShortTree {
 }
 
 impl ShortTree {
+    pub fn new(root_id: usize, child_ids: Vec<usize>) -> ShortTree {
+        ShortTree {
+            root_id: root_id,
+            child_ids: child_ids,
+        }
+    }
+
     pub fn get_root_id(&self) -> usize {
         self.root_id
     }
@@ -1,10 +1,20 @@
+#![allow(
+    clippy::needless_late_init,
+    clippy::needless_return,
+    clippy::redundant_static_lifetimes
+)]
+
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs::read_to_string;
+use std::str::FromStr;
 
 use linked_hash_map::{Entries, LinkedHashMap};
 use yaml_rust::{yaml::Yaml, YamlLoader};
 
+use crate::components::{Attributes, Graph};
 use crate::swc_parser::SWCCompartmentKind;
-use crate::writer::{Indent, StringBuffer, ToDot};
+use crate::writer::{EdgeDirection, Indent, Projection, RadiusScale, StringBuffer, ToDot};
 
 static OPTION_GROUPS: &'static [&'static str] = &[
     "soma",
@@ -17,12 +27,26 @@ static OPTION_GROUPS: &'static [&'static str] = &[
 
 pub struct Config {
     option_groups: LinkedHashMap<&'static str, ConfigOptionGroup>,
+    /// Options for vertices within a range of centrifugal branch orders, in
+    /// the order they were given.
+    branch_order_groups: Vec<(OrderRange, ConfigOptionGroup)>,
+    /// Options for vertices within a range of Strahler orders, in the order
+    /// they were given.
+    strahler_order_groups: Vec<(OrderRange, ConfigOptionGroup)>,
+    projection: Option<Projection>,
+    radius_scale: Option<RadiusScale>,
+    edge_direction: EdgeDirection,
 }
 
 impl Config {
     pub fn new() -> Result<Config, YamlParseError> {
         let mut config = Config {
             option_groups: LinkedHashMap::new(),
+            branch_order_groups: Vec::new(),
+            strahler_order_groups: Vec::new(),
+            projection: None,
+            radius_scale: None,
+            edge_direction: EdgeDirection::Undirected,
         };
         for group in OPTION_GROUPS {
             config.option_groups.insert(group, ConfigOptionGroup::new());
@@ -38,19 +62,68 @@ impl Config {
     }
 
     pub fn try_overload_from_file(&mut self, filename: &str) -> Result<(), YamlParseError> {
-        let mut yaml = Config::try_parse_yaml_file(filename)?;
+        let yaml = Config::try_parse_yaml_file(filename)?;
         self.try_overload_from_yaml(yaml)
     }
 
     pub fn get_config(&self, group: SWCCompartmentKind) -> &ConfigOptionGroup {
-        match group {
-            SWCCompartmentKind::Soma => &self.option_groups["soma"],
-            SWCCompartmentKind::Axon => &self.option_groups["axon"],
-            SWCCompartmentKind::Dendrite => &self.option_groups["dendrite"],
-            SWCCompartmentKind::ApicalDendrite => &self.option_groups["apicaldendrite"],
-            SWCCompartmentKind::Undefined => &self.option_groups["undefined"],
-            SWCCompartmentKind::Custom => &self.option_groups["custom"],
+        &self.option_groups[group.get_name()]
+    }
+
+    /// Get attributes of vertices selected by `branch_order` and
+    /// `strahler_order` option groups, keyed by vertex id.
+    ///
+    /// Later groups take precedence over earlier ones, and `strahler_order`
+    /// groups take precedence over `branch_order` groups. Vertices that are
+    /// not selected by any group are left out.
+    pub fn get_order_attributes(&self, graph: &Graph) -> BTreeMap<usize, Attributes> {
+        let mut attributes = BTreeMap::<usize, Attributes>::new();
+        if !self.branch_order_groups.is_empty() {
+            select_by_order(
+                &mut attributes,
+                &graph.get_branch_orders(),
+                &self.branch_order_groups,
+            );
+        }
+        if !self.strahler_order_groups.is_empty() {
+            select_by_order(
+                &mut attributes,
+                &graph.get_strahler_orders(),
+                &self.strahler_order_groups,
+            );
         }
+        return attributes;
+    }
+
+    /// Get the projection used to pin vertices to their anatomical positions.
+    ///
+    /// Vertices are not given positions if this is `None`.
+    pub fn get_projection(&self) -> Option<&Projection> {
+        self.projection.as_ref()
+    }
+
+    pub fn set_projection(&mut self, projection: Option<Projection>) {
+        self.projection = projection;
+    }
+
+    /// Get the scale used to size vertices and edges by compartment radius.
+    ///
+    /// Vertices and edges are not sized by radius if this is `None`.
+    pub fn get_radius_scale(&self) -> Option<&RadiusScale> {
+        self.radius_scale.as_ref()
+    }
+
+    pub fn set_radius_scale(&mut self, radius_scale: Option<RadiusScale>) {
+        self.radius_scale = radius_scale;
+    }
+
+    /// Get whether edges are undirected or which way they point.
+    pub fn get_edge_direction(&self) -> EdgeDirection {
+        self.edge_direction
+    }
+
+    pub fn set_edge_direction(&mut self, edge_direction: EdgeDirection) {
+        self.edge_direction = edge_direction;
     }
 
     /// Load the contents of a file as a Yaml object.
@@ -60,6 +133,7 @@ impl Config {
         return Ok(yaml_object);
     }
 
+    #[allow(clippy::expect_fun_call, clippy::explicit_auto_deref)]
     fn try_overload_from_yaml(&mut self, yaml: Yaml) -> Result<(), YamlParseError> {
         // Check whether YAML config file contains a hash (which it should)
         match yaml {
@@ -68,7 +142,7 @@ impl Config {
                 for group in OPTION_GROUPS {
                     // Check whether each config option is there.
                     match top_level_hash.get_mut(&Yaml::from_str(*group)) {
-                        Some(mut yaml) => {
+                        Some(yaml) => {
                             // Check whether config option is a Hash, if it exists.
                             match yaml {
                                 // If it is a hash, parse it.
@@ -95,6 +169,14 @@ impl Config {
                         None => continue,
                     }
                 }
+                if let Some(yaml) = top_level_hash.get(&Yaml::from_str("branch_order")) {
+                    let groups = parse_order_groups("branch_order", yaml)?;
+                    self.branch_order_groups.extend(groups);
+                }
+                if let Some(yaml) = top_level_hash.get(&Yaml::from_str("strahler_order")) {
+                    let groups = parse_order_groups("strahler_order", yaml)?;
+                    self.strahler_order_groups.extend(groups);
+                }
             }
             _ => {
                 return Err(YamlParseError::WrongType(
@@ -106,6 +188,7 @@ impl Config {
         return Ok(());
     }
 
+    #[allow(clippy::needless_borrow)]
     fn try_parse_yaml(yaml_string: &str) -> Result<Yaml, YamlParseError> {
         let config;
         match YamlLoader::load_from_str(&yaml_string) {
@@ -145,14 +228,14 @@ mod config_tests {
         match Config::new() {
             Ok(_) => {}
             Err(parse_error) => match parse_error {
-                YamlParseError::WrongType(msg) => panic!(format!(
+                YamlParseError::WrongType(msg) => panic!(
                     "Could not construct Config due to `YamlParseError::Wrongtype`: {}",
                     msg
-                )),
-                YamlParseError::FileRead(msg) => panic!(format!(
+                ),
+                YamlParseError::FileRead(msg) => panic!(
                     "Could not construct Config due to `YamlParseError::FileRead`: {}",
                     msg
-                )),
+                ),
                 YamlParseError::BadValue => {
                     panic!("Could not construct Config due to `YamlParseError::BadValue`")
                 }
@@ -180,7 +263,23 @@ impl ConfigOptionGroup {
     }
 }
 
+impl ConfigOptionGroup {
+    /// Get options with values as vertex attributes.
+    ///
+    /// Options without a value are left out.
+    pub fn to_attributes(&self) -> Attributes {
+        let mut attributes = Attributes::new();
+        for (key, val) in self.options.iter() {
+            if let Some(val) = val {
+                attributes.insert(key, val.clone());
+            }
+        }
+        return attributes;
+    }
+}
+
 impl ToDot for ConfigOptionGroup {
+    #[allow(clippy::needless_borrow)]
     fn to_dot(&self, leading_newline: bool, indent: Indent) -> String {
         let mut config_string = StringBuffer::new(leading_newline, indent, 256);
 
@@ -244,19 +343,197 @@ fn parse_config_entries(
     return Ok(group);
 }
 
+/// A range of branch or Strahler orders, written `2`, `2-4` or `3+`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct OrderRange {
+    min: usize,
+    /// Largest order in the range, or `None` if there is no upper bound.
+    max: Option<usize>,
+}
+
+impl OrderRange {
+    fn contains(&self, order: usize) -> bool {
+        order >= self.min && self.max.map_or(true, |max| order <= max)
+    }
+}
+
+impl FromStr for OrderRange {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<OrderRange, ()> {
+        let s = s.trim();
+        let range = if let Some(min) = s.strip_suffix('+') {
+            OrderRange {
+                min: min.trim().parse().map_err(|_| ())?,
+                max: None,
+            }
+        } else if let Some((min, max)) = s.split_once('-') {
+            OrderRange {
+                min: min.trim().parse().map_err(|_| ())?,
+                max: Some(max.trim().parse().map_err(|_| ())?),
+            }
+        } else {
+            let order = s.parse().map_err(|_| ())?;
+            OrderRange {
+                min: order,
+                max: Some(order),
+            }
+        };
+        return Ok(range);
+    }
+}
+
+/// Add the options of every group whose range contains the order of a vertex
+/// to that vertex's attributes.
+fn select_by_order(
+    attributes: &mut BTreeMap<usize, Attributes>,
+    orders: &BTreeMap<usize, usize>,
+    groups: &[(OrderRange, ConfigOptionGroup)],
+) {
+    for (id, order) in orders.iter() {
+        for (range, group) in groups.iter() {
+            if range.contains(*order) {
+                attributes
+                    .entry(*id)
+                    .or_default()
+                    .extend(&group.to_attributes());
+            }
+        }
+    }
+}
+
+/// Parse a `Yaml::Hash` of order ranges and option groups, eg
+/// `{1: {color: red}, 2+: {color: blue}}`.
+fn parse_order_groups(
+    name: &str,
+    yaml: &Yaml,
+) -> Result<Vec<(OrderRange, ConfigOptionGroup)>, YamlParseError> {
+    let hash = match yaml {
+        Yaml::Hash(hash) => hash,
+        _ => {
+            return Err(YamlParseError::WrongType(format!(
+                "Expected config group {} to be a hash.",
+                name
+            )))
+        }
+    };
+    let mut groups = Vec::with_capacity(hash.len());
+    for (key, value) in hash.iter() {
+        let range = match key {
+            Yaml::Integer(order) if *order >= 0 => Ok(OrderRange {
+                min: *order as usize,
+                max: Some(*order as usize),
+            }),
+            Yaml::String(range) => range.parse::<OrderRange>(),
+            _ => Err(()),
+        };
+        let range = range.map_err(|_| {
+            YamlParseError::WrongType(format!(
+                "Expected keys of config group {} to be orders like `2`, `2-4` or `3+`.",
+                name
+            ))
+        })?;
+        let group = match value {
+            Yaml::Hash(options) => parse_config_entries(&mut options.clone().entries())?,
+            _ => {
+                return Err(YamlParseError::WrongType(format!(
+                    "Expected entries of config group {} to be hashes.",
+                    name
+                )))
+            }
+        };
+        groups.push((range, group));
+    }
+    return Ok(groups);
+}
+
+#[cfg(test)]
+mod order_groups_tests {
+    use super::*;
+    use crate::test_utils::{get_test_config, graph_from_swc};
+
+    fn load_yaml(string: &str) -> Yaml {
+        YamlLoader::load_from_str(string).expect("Could not load yaml")[0].clone()
+    }
+
+    #[test]
+    fn parse_order_range() {
+        assert_eq!(
+            "3".parse::<OrderRange>(),
+            Ok(OrderRange {
+                min: 3,
+                max: Some(3)
+            })
+        );
+        assert_eq!(
+            "2-4".parse::<OrderRange>(),
+            Ok(OrderRange {
+                min: 2,
+                max: Some(4)
+            })
+        );
+        assert_eq!(
+            "3+".parse::<OrderRange>(),
+            Ok(OrderRange { min: 3, max: None })
+        );
+        assert!("x".parse::<OrderRange>().is_err());
+    }
+
+    #[test]
+    fn order_groups_select_vertices() {
+        let mut config = get_test_config();
+        let yaml = load_yaml(
+            "branch_order:\n  1: {color: red}\n  2+: {color: blue, label: far}\n\
+             strahler_order:\n  1: {color: green}",
+        );
+        if let Err(err) = config.try_overload_from_yaml(yaml) {
+            panic!("Could not load order groups: {}", err);
+        }
+
+        // 1 (soma) - 2 -+- 3
+        //               +- 4
+        let swc = "1 1 0 0 0 1 -1\n2 3 0 1 0 1 1\n3 3 0 2 0 1 2\n4 3 1 2 0 1 2\n";
+        let attributes = config.get_order_attributes(&graph_from_swc(swc));
+
+        assert!(!attributes.contains_key(&1));
+        assert_eq!(attributes[&2].get("color"), Some(&"red".to_string()));
+        assert_eq!(attributes[&3].get("color"), Some(&"green".to_string()));
+        assert_eq!(attributes[&3].get("label"), Some(&"far".to_string()));
+    }
+
+    #[test]
+    fn bad_order_is_an_error() {
+        let mut config = get_test_config();
+        let yaml = load_yaml("branch_order:\n  first: {color: red}");
+        assert!(config.try_overload_from_yaml(yaml).is_err());
+    }
+}
+
 pub enum YamlParseError {
     /// Yaml enum is not the expected variant (see `yaml_rust::yaml::Yaml`).
     WrongType(String),
     /// Yaml object does not exist (see `yaml_rust::yaml::Yaml::BadValue`).
+    #[allow(dead_code)]
     BadValue,
     /// Could not read Yaml from a file.
     FileRead(String),
 }
 
+impl fmt::Display for YamlParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            YamlParseError::WrongType(msg) => write!(f, "{}", msg),
+            YamlParseError::BadValue => write!(f, "missing value in configuration"),
+            YamlParseError::FileRead(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
 #[cfg(test)]
 mod parse_config_entries_tests {
     use super::*;
 
+    #[allow(clippy::expect_fun_call)]
     fn load_hash_from_str(string: &str) -> LinkedHashMap<Yaml, Yaml> {
         let doc = YamlLoader::load_from_str(string)
             .expect(&format!("Could not load {} as a yaml string", string))[0]
@@ -1,58 +1,588 @@
-use std::fs::File;
-use std::io::Write;
+use std::fs::{create_dir_all, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use std::thread;
 
+use clap::ArgMatches;
+
+mod analysis;
+mod batch;
 mod cli_parser;
 mod components;
 mod config;
 mod swc_parser;
+#[cfg(test)]
+mod test_utils;
 mod writer;
 
-use cli_parser::{get_cli_arguments, get_filename_without_extension};
+use analysis::{
+    add_order_attributes, add_segment_attributes, compute_vertex_metric, get_sholl_shells,
+    Morphometrics, ShollProfile, VertexMetric, DEFAULT_SHOLL_STEP,
+};
+use batch::{collect_input_files, get_output_paths, run_in_order, BatchSummary};
+use cli_parser::{get_cli_arguments, get_separator, parse_range};
 use components::Graph;
 use config::Config;
-use swc_parser::parse_file;
-use writer::{ConfiguredToDot, Indent};
+use swc_parser::{
+    parse_file_with_mode, parse_reader_with_mode, ParseMode, ParseReport, RepairPolicy, SWCNeuron,
+    SWCParseError,
+};
+use writer::{
+    sholl_to_string, stats_to_string, ColorScale, Colormap, ConfiguredToDot, ConfiguredToGEXF,
+    EdgeDirection, GraphFormat, Indent, Projection, RadiusMapping, RadiusScale, SWCFormat,
+    ShollFormat, StatsFormat, ToGML, ToGraphML, ToJSON, ToPajek, ToSWC, ToTable,
+};
+
+/// File name that stands for stdin or stdout.
+static STDIO: &str = "-";
 
 fn main() {
     let cli_matches = get_cli_arguments();
+    match cli_matches.subcommand() {
+        ("standardize", Some(standardize_matches)) => {
+            let format = get_swc_format(standardize_matches);
+            process_inputs(standardize_matches, "_standardized.swc", |neuron| {
+                Graph::from(neuron).standardize().to_swc(&format)
+            });
+        }
+        ("stats", Some(stats_matches)) => {
+            let format = match stats_matches.value_of("format") {
+                Some("json") => StatsFormat::Json,
+                Some("csv") => StatsFormat::Csv,
+                _ => StatsFormat::Text,
+            };
+            let (records, all_succeeded) = measure_inputs(stats_matches, |neuron| {
+                Ok(Morphometrics::measure(&Graph::from(neuron)))
+            });
+            let output_file_name = stats_matches.value_of("output").unwrap_or(STDIO);
+            write_output(output_file_name, &stats_to_string(&records, format));
+            if !all_succeeded {
+                process::exit(1);
+            }
+        }
+        ("sholl", Some(sholl_matches)) => {
+            let format = match sholl_matches.value_of("format") {
+                Some("json") => ShollFormat::Json,
+                _ => ShollFormat::Csv,
+            };
+            let step = get_sholl_step(sholl_matches, "step");
+            let (records, all_succeeded) = measure_inputs(sholl_matches, |neuron| {
+                ShollProfile::measure(&Graph::from(neuron), step)
+            });
+            let output_file_name = sholl_matches.value_of("output").unwrap_or(STDIO);
+            let by_kind = sholl_matches.is_present("by-kind");
+            write_output(output_file_name, &sholl_to_string(&records, format, by_kind));
+            if !all_succeeded {
+                process::exit(1);
+            }
+        }
+        _ => {
+            let config = get_config(&cli_matches);
+            let vertex_metrics = cli_matches.is_present("vertex-metrics");
+            let edge_metrics = cli_matches.is_present("edge-metrics");
+            let len_scale = get_len_scale(&cli_matches);
+            let coloring = get_coloring(&cli_matches);
+            let sholl_shells = cli_matches.is_present("sholl-shells");
+            let sholl_step = get_sholl_step(&cli_matches, "sholl-step");
+            let collapse = cli_matches.is_present("collapse");
+            let format = get_graph_format(&cli_matches);
+            let delimiter = get_delimiter(&cli_matches, format);
+            let swc_format = get_swc_format(&cli_matches);
+            let mut suffix = format.get_extension().to_string();
+            if is_table(format) && delimiter == "\t" {
+                suffix = suffix.replace(".csv", ".tsv");
+            }
+            process_inputs(&cli_matches, &suffix, |neuron| {
+                let mut graph = Graph::from(neuron);
+                if vertex_metrics {
+                    add_order_attributes(&mut graph);
+                }
+                if sholl_shells {
+                    for (id, shell) in get_sholl_shells(&graph, sholl_step) {
+                        graph.set_vertex_number(id, "sholl_shell", shell as f64);
+                    }
+                }
+                if let Some((metric, color_scale)) = &coloring {
+                    let values = compute_vertex_metric(&graph, *metric);
+                    color_scale.add_vertex_colors(&mut graph, &values);
+                }
+                // Collapse after measuring vertices so that measurements
+                // like path distance follow the full morphology.
+                if collapse {
+                    graph = graph.collapse();
+                }
+                // ...but measure edges after collapsing so that collapsed
+                // edges get the measurements of their whole chain.
+                if edge_metrics || len_scale.is_some() {
+                    add_segment_attributes(&mut graph, edge_metrics, len_scale);
+                }
+                match format {
+                    GraphFormat::Dot => graph.to_dot(false, Indent::flat(0), &config),
+                    GraphFormat::GraphML => graph.to_graphml(config.get_edge_direction()),
+                    GraphFormat::Gexf => graph.to_gexf(&config),
+                    GraphFormat::Json => graph.to_json(config.get_edge_direction()),
+                    GraphFormat::Gml => graph.to_gml(config.get_edge_direction()),
+                    GraphFormat::Pajek => graph.to_pajek(config.get_edge_direction()),
+                    GraphFormat::EdgeList => graph.to_edge_list(&delimiter),
+                    GraphFormat::Nodes => graph.to_node_table(&delimiter),
+                    GraphFormat::Swc => graph.to_swc(&swc_format),
+                }
+            });
+        }
+    }
+}
+
+#[allow(clippy::needless_return)]
+fn get_config(cli_matches: &ArgMatches) -> Config {
     let mut config: Config;
     match Config::new() {
         Ok(c) => config = c,
-        _ => panic!("Could not load default config"),
+        Err(err) => exit_with_error(&format!("could not load default config: {}", err)),
+    }
+    if let Some(config_file) = cli_matches.value_of("config") {
+        if let Err(err) = config.try_overload_from_file(config_file) {
+            exit_with_error(&err.to_string());
+        }
     }
-    match cli_matches.value_of("config") {
-        Some(config_file) => {
-            config.try_overload_from_file(&config_file.to_string());
+    if let Some(projection) = cli_matches.value_of("pos") {
+        let mut projection = projection
+            .parse::<Projection>()
+            .expect("Projection should have been validated by the CLI parser.");
+        if let Some(scale) = cli_matches.value_of("pos-scale") {
+            projection = projection.with_scale(
+                scale
+                    .parse::<f64>()
+                    .expect("Scale should have been validated by the CLI parser."),
+            );
         }
-        None => {}
+        config.set_projection(Some(projection));
     }
+    if let Some(mapping) = cli_matches.value_of("radius-scale") {
+        let mut radius_scale = RadiusScale::new(
+            mapping
+                .parse::<RadiusMapping>()
+                .expect("Radius scale should have been validated by the CLI parser."),
+        );
+        if let Some(factor) = cli_matches.value_of("radius-factor") {
+            radius_scale = radius_scale.with_factor(
+                factor
+                    .parse::<f64>()
+                    .expect("Radius factor should have been validated by the CLI parser."),
+            );
+        }
+        if let Some(range) = cli_matches.value_of("radius-range") {
+            let (min, max) =
+                parse_range(range).expect("Range should have been validated by the CLI parser.");
+            radius_scale = radius_scale.with_range(min, max);
+        }
+        config.set_radius_scale(Some(radius_scale));
+    }
+    if cli_matches.is_present("directed") {
+        match cli_matches.value_of("directed") {
+            Some("child-to-parent") => config.set_edge_direction(EdgeDirection::ChildToParent),
+            _ => config.set_edge_direction(EdgeDirection::ParentToChild),
+        }
+    }
+    return config;
+}
 
-    let input_file_name = cli_matches
-        .value_of("INPUT")
-        .expect("Required argument INPUT is missing.")
-        .to_string();
-    let swcneuron = parse_file(input_file_name.clone());
-    let graphneuron = Graph::from(swcneuron);
-
-    // Get the name of the output file
-    // Fall back to the name of the input file with .dot suffix if none is provided.
-    let mut output_file_name: String;
-    match cli_matches.value_of("output") {
-        Some(file_name) => output_file_name = file_name.to_string(),
+/// Get the format to write graphs in.
+///
+/// If `--format` isn't given, the format is inferred from the extension of
+/// `--output`, falling back on DOT if there is no extension. Exits with an
+/// error if the extension isn't that of a known format.
+fn get_graph_format(cli_matches: &ArgMatches) -> GraphFormat {
+    match cli_matches.value_of("format") {
+        Some("graphml") => GraphFormat::GraphML,
+        Some("gexf") => GraphFormat::Gexf,
+        Some("json") => GraphFormat::Json,
+        Some("gml") => GraphFormat::Gml,
+        Some("pajek") => GraphFormat::Pajek,
+        Some("edgelist") => GraphFormat::EdgeList,
+        Some("nodes") => GraphFormat::Nodes,
+        Some("swc") => GraphFormat::Swc,
+        Some(_) => GraphFormat::Dot,
+        None => match cli_matches.value_of("output") {
+            Some(output) => match Path::new(output).extension() {
+                Some(extension) => GraphFormat::from_extension(&extension.to_string_lossy())
+                    .unwrap_or_else(|| {
+                        exit_with_error(&format!(
+                            "can't tell the output format from the extension of {}; use --format",
+                            output
+                        ))
+                    }),
+                None => GraphFormat::Dot,
+            },
+            None => GraphFormat::Dot,
+        },
+    }
+}
+
+/// Check whether graphs in a format are written as delimited tables.
+fn is_table(format: GraphFormat) -> bool {
+    format == GraphFormat::EdgeList || format == GraphFormat::Nodes
+}
+
+/// Get the column delimiter for edge lists and node tables.
+///
+/// If `--delimiter` isn't given, tabs are used when `--output` ends in
+/// `.tsv` and commas otherwise. Exits with an error if `--delimiter` is given
+/// for a format that isn't a table.
+fn get_delimiter(cli_matches: &ArgMatches, format: GraphFormat) -> String {
+    match cli_matches.value_of("delimiter") {
+        Some(_) if !is_table(format) => {
+            exit_with_error("--delimiter can only be used with --format edgelist or nodes")
+        }
+        Some(delimiter) => get_separator(delimiter),
         None => {
-            output_file_name = get_filename_without_extension(input_file_name);
-            output_file_name.push_str(".dot");
+            let is_tsv = cli_matches
+                .value_of("output")
+                .and_then(|output| Path::new(output).extension())
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("tsv"));
+            if is_tsv {
+                "\t".to_string()
+            } else {
+                ",".to_string()
+            }
         }
     }
+}
+
+/// Get the number of points per SWC unit to use for Graphviz `len`, if any.
+#[allow(clippy::needless_return)]
+fn get_len_scale(cli_matches: &ArgMatches) -> Option<f64> {
+    if !cli_matches.is_present("edge-len") {
+        return None;
+    }
+    let len_scale = match cli_matches.value_of("edge-len") {
+        Some(scale) => scale
+            .parse::<f64>()
+            .expect("Scale should have been validated by the CLI parser."),
+        None => 1.0,
+    };
+    return Some(len_scale);
+}
+
+/// Get the metric and color scale to color vertices with, if any.
+#[allow(clippy::needless_return)]
+fn get_coloring(cli_matches: &ArgMatches) -> Option<(VertexMetric, ColorScale)> {
+    let metric = match cli_matches.value_of("color-by") {
+        Some(metric) => metric
+            .parse::<VertexMetric>()
+            .expect("Metric should have been validated by the CLI parser."),
+        None => return None,
+    };
+    let metric = match metric {
+        VertexMetric::ShollShell(_) => {
+            VertexMetric::ShollShell(get_sholl_step(cli_matches, "sholl-step"))
+        }
+        metric => metric,
+    };
+    let mut color_scale = ColorScale::new(match cli_matches.value_of("colormap") {
+        Some(colormap) => colormap
+            .parse::<Colormap>()
+            .expect("Colormap should have been validated by the CLI parser."),
+        None => Colormap::Viridis,
+    });
+    if let Some(range) = cli_matches.value_of("color-range") {
+        let (min, max) =
+            parse_range(range).expect("Range should have been validated by the CLI parser.");
+        color_scale = color_scale.with_range(min, max);
+    }
+    return Some((metric, color_scale));
+}
 
-    let mut f = File::create(&output_file_name).expect(&format!(
-        "Could not create output file {}.",
-        &output_file_name
-    ));
-    f.write(
-        &graphneuron
-            .to_dot(false, Indent::flat(0), &config)
-            .into_bytes(),
+/// Get the distance between Sholl spheres given by an option.
+fn get_sholl_step(cli_matches: &ArgMatches, option: &str) -> f64 {
+    match cli_matches.value_of(option) {
+        Some(step) => step
+            .parse::<f64>()
+            .expect("Step should have been validated by the CLI parser."),
+        None => DEFAULT_SHOLL_STEP,
+    }
+}
+
+#[allow(clippy::needless_return)]
+fn get_swc_format(cli_matches: &ArgMatches) -> SWCFormat {
+    let mut format = SWCFormat::default();
+    if let Some(precision) = cli_matches.value_of("precision") {
+        format.precision = Some(
+            precision
+                .parse::<usize>()
+                .expect("Precision should have been validated by the CLI parser."),
+        );
+    }
+    if let Some(separator) = cli_matches.value_of("separator") {
+        format.separator = get_separator(separator);
+    }
+    return format;
+}
+
+fn get_parse_mode(cli_matches: &ArgMatches) -> ParseMode {
+    if cli_matches.is_present("lenient") {
+        match cli_matches.value_of("lenient") {
+            Some("skip") => ParseMode::Lenient(RepairPolicy::Skip),
+            _ => ParseMode::Lenient(RepairPolicy::Repair),
+        }
+    } else {
+        ParseMode::Strict
+    }
+}
+
+/// Convert every INPUT with `render` and write the results.
+///
+/// A single INPUT is written to `--output` if one is given. Otherwise,
+/// outputs are placed next to their inputs (or mirrored into `--output-dir`)
+/// with `suffix` in place of the file extension. Exits with a nonzero status
+/// if any INPUT could not be converted.
+fn process_inputs<F: Fn(SWCNeuron) -> String + Sync>(
+    cli_matches: &ArgMatches,
+    suffix: &str,
+    render: F,
+) {
+    let parse_mode = get_parse_mode(cli_matches);
+    let input_specs: Vec<&str> = cli_matches
+        .values_of("INPUT")
+        .expect("Required argument INPUT is missing.")
+        .collect();
+
+    // stdin can only be used on its own.
+    if input_specs.contains(&STDIO) {
+        if input_specs.len() > 1 {
+            exit_with_error("`-` (stdin) can't be combined with other inputs");
+        }
+        let stdin = io::stdin();
+        let reader = stdin.lock();
+        let output_file_name = cli_matches.value_of("output").unwrap_or(STDIO);
+        let mut diagnostics = String::new();
+        let parse_result = parse_reader_with_mode(reader, "<stdin>", parse_mode);
+        let neuron = parse_and_report(parse_result, &mut diagnostics);
+        eprint!("{}", diagnostics);
+        match neuron {
+            Ok(neuron) => write_output(output_file_name, &render(neuron)),
+            Err(_) => process::exit(1),
+        }
+        return;
+    }
+
+    let input_files = match collect_input_files(&input_specs) {
+        Ok(input_files) => input_files,
+        Err(err) => exit_with_error(&err),
+    };
+    let is_batch = input_files.len() > 1;
+    if is_batch && cli_matches.is_present("output") {
+        exit_with_error("--output can only be used with a single input file; use --output-dir");
+    }
+    let output_dir = cli_matches.value_of("output-dir").map(Path::new);
+
+    let output_file_names: Vec<String> = if cli_matches.is_present("stdout") {
+        vec![STDIO.to_string(); input_files.len()]
+    } else if let Some(file_name) = cli_matches.value_of("output") {
+        vec![file_name.to_string()]
+    } else {
+        match get_output_paths(&input_files, output_dir, suffix) {
+            Ok(output_paths) => output_paths
+                .iter()
+                .map(|output_path| output_path.to_string_lossy().to_string())
+                .collect(),
+            Err(err) => exit_with_error(&err),
+        }
+    };
+
+    // Files are converted in parallel, but diagnostics and outputs are
+    // written one file at a time in input order.
+    let mut summary = BatchSummary::new();
+    run_in_order(
+        &input_files,
+        get_jobs(cli_matches),
+        |input_file| {
+            let input_file_name = input_file.path.to_string_lossy().to_string();
+            let mut diagnostics = String::new();
+            let parse_result = parse_file_with_mode(input_file_name, parse_mode);
+            let output = parse_and_report(parse_result, &mut diagnostics).map(&render);
+            (diagnostics, output)
+        },
+        |i, (diagnostics, output)| {
+            eprint!("{}", diagnostics);
+            let input_path = &input_files[i].path;
+            match output.and_then(|contents| try_write_output(&output_file_names[i], &contents)) {
+                Ok(_) => summary.record_success(),
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    summary.record_failure(input_path, err);
+                }
+            }
+        },
     );
-    f.flush();
+
+    if is_batch {
+        eprintln!("{}", summary.to_string());
+    }
+    if summary.has_failures() {
+        process::exit(1);
+    }
+}
+
+/// Measure every INPUT with `measure`.
+///
+/// Returns the name of each file that could be measured along with its
+/// measurements, in input order, and whether every INPUT could be parsed and
+/// measured.
+#[allow(clippy::needless_return)]
+fn measure_inputs<T: Send, F: Fn(SWCNeuron) -> Result<T, String> + Sync>(
+    cli_matches: &ArgMatches,
+    measure: F,
+) -> (Vec<(String, T)>, bool) {
+    let parse_mode = get_parse_mode(cli_matches);
+    let input_specs: Vec<&str> = cli_matches
+        .values_of("INPUT")
+        .expect("Required argument INPUT is missing.")
+        .collect();
+
+    // stdin can only be used on its own.
+    if input_specs.contains(&STDIO) {
+        if input_specs.len() > 1 {
+            exit_with_error("`-` (stdin) can't be combined with other inputs");
+        }
+        let stdin = io::stdin();
+        let mut diagnostics = String::new();
+        let parse_result = parse_reader_with_mode(stdin.lock(), "<stdin>", parse_mode);
+        let neuron = parse_and_report(parse_result, &mut diagnostics);
+        eprint!("{}", diagnostics);
+        match neuron.map(&measure) {
+            Ok(Ok(measurements)) => return (vec![("<stdin>".to_string(), measurements)], true),
+            Ok(Err(err)) => exit_with_error(&err),
+            Err(_) => process::exit(1),
+        }
+    }
+
+    let input_files = match collect_input_files(&input_specs) {
+        Ok(input_files) => input_files,
+        Err(err) => exit_with_error(&err),
+    };
+    let mut records = Vec::with_capacity(input_files.len());
+    let mut summary = BatchSummary::new();
+    run_in_order(
+        &input_files,
+        get_jobs(cli_matches),
+        |input_file| {
+            let input_file_name = input_file.path.to_string_lossy().to_string();
+            let mut diagnostics = String::new();
+            let parse_result = parse_file_with_mode(input_file_name, parse_mode);
+            let output = parse_and_report(parse_result, &mut diagnostics).and_then(&measure);
+            (diagnostics, output)
+        },
+        |i, (diagnostics, output)| {
+            eprint!("{}", diagnostics);
+            let input_path = &input_files[i].path;
+            match output {
+                Ok(measurements) => {
+                    summary.record_success();
+                    records.push((input_path.to_string_lossy().to_string(), measurements));
+                }
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    summary.record_failure(input_path, err);
+                }
+            }
+        },
+    );
+
+    if input_files.len() > 1 {
+        eprintln!("{}", summary.to_string());
+    }
+    return (records, !summary.has_failures());
+}
+
+/// Describe any problems found while parsing an SWC file in `diagnostics`.
+///
+/// Returns the reason parsing failed if the file could not be parsed.
+fn parse_and_report(
+    parse_result: Result<(SWCNeuron, ParseReport), SWCParseError>,
+    diagnostics: &mut String,
+) -> Result<SWCNeuron, String> {
+    match parse_result {
+        Ok((neuron, report)) => {
+            for entry in report.iter() {
+                diagnostics.push_str(&format!("{}\n\n", entry.to_diagnostic()));
+            }
+            if !report.is_empty() {
+                diagnostics.push_str(&format!(
+                    "{} error(s), {} warning(s)\n",
+                    report.error_count(),
+                    report.warning_count()
+                ));
+            }
+            Ok(neuron)
+        }
+        Err(err) => {
+            diagnostics.push_str(&format!("{}\n", err.to_diagnostic()));
+            Err(err.message())
+        }
+    }
+}
+
+/// Get the number of worker threads to use for a batch.
+///
+/// Zero means one thread per available CPU core.
+#[allow(clippy::needless_return)]
+fn get_jobs(cli_matches: &ArgMatches) -> usize {
+    let jobs = cli_matches.value_of("jobs").map_or(1, |jobs| {
+        jobs.parse::<usize>()
+            .expect("Jobs should have been validated by the CLI parser.")
+    });
+    if jobs == 0 {
+        return thread::available_parallelism().map_or(1, |cores| cores.get());
+    }
+    return jobs;
+}
+
+/// Write `contents` to a file, or to stdout if `output_file_name` is `-`.
+///
+/// Exits if the output can't be written.
+fn write_output(output_file_name: &str, contents: &str) {
+    if let Err(err) = try_write_output(output_file_name, contents) {
+        exit_with_error(&err);
+    }
+}
+
+/// Write `contents` to a file, or to stdout if `output_file_name` is `-`.
+///
+/// Missing parent directories of the output file are created.
+fn try_write_output(output_file_name: &str, contents: &str) -> Result<(), String> {
+    let write_result = if output_file_name == STDIO {
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        handle
+            .write_all(contents.as_bytes())
+            .and_then(|_| handle.flush())
+    } else {
+        let parent_dir = Path::new(output_file_name)
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty());
+        parent_dir
+            .map_or(Ok(()), create_dir_all)
+            .and_then(|_| File::create(output_file_name))
+            .and_then(|mut f| {
+                f.write_all(contents.as_bytes())?;
+                f.flush()
+            })
+    };
+    match write_result {
+        Ok(_) => Ok(()),
+        // The reader hung up early (eg `swc2dot - | head`), which is fine.
+        Err(ref err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        Err(err) if output_file_name == STDIO => Err(format!("could not write to stdout: {}", err)),
+        Err(err) => Err(format!(
+            "could not write output file {}: {}",
+            output_file_name, err
+        )),
+    }
+}
+
+/// Print an error message to stderr and exit with a nonzero status.
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
 }
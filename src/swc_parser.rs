@@ -1,40 +1,412 @@
-use std::collections::{
-    btree_map::{Entry, Iter},
-    BTreeMap,
-};
+#![allow(
+    clippy::needless_late_init,
+    clippy::needless_return,
+    clippy::redundant_field_names,
+    clippy::redundant_static_lifetimes
+)]
+
+use std::cmp::max;
+use std::collections::{btree_map::Iter, BTreeMap, BTreeSet};
+use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::str::FromStr;
 
-pub fn parse_file(file_name: String) -> SWCNeuron {
-    let reader = get_file_reader(file_name);
-    match parse_lines(reader) {
-        Ok(neuron) => neuron,
-        Err(msg) => panic!(msg),
-    }
+use itertools::Itertools;
+use linked_hash_map::LinkedHashMap;
+
+/// Parse an SWC file, collecting line-level problems into a `ParseReport`.
+///
+/// In `ParseMode::Strict` the first problem is returned as an `Err` and the
+/// report is always empty. In `ParseMode::Lenient` only I/O errors are fatal.
+pub fn parse_file_with_mode(
+    file_name: String,
+    mode: ParseMode,
+) -> Result<(SWCNeuron, ParseReport), SWCParseError> {
+    let reader = get_file_reader(&file_name)?;
+    parse_lines(reader, &file_name, mode)
+}
+
+/// Parse SWC from any buffered reader, such as `stdin`.
+///
+/// `source_name` is used in place of a file name in error messages.
+///
+/// # See also
+///
+/// - `parse_file_with_mode()`
+pub fn parse_reader_with_mode<R: BufRead>(
+    reader: R,
+    source_name: &str,
+    mode: ParseMode,
+) -> Result<(SWCNeuron, ParseReport), SWCParseError> {
+    parse_lines(reader, source_name, mode)
 }
 
-fn get_file_reader(file_name: String) -> BufReader<File> {
-    let f = File::open(file_name).expect("Could not open file.");
-    let reader = BufReader::new(f);
-    return reader;
+fn get_file_reader(file_name: &str) -> Result<BufReader<File>, SWCParseError> {
+    match File::open(file_name) {
+        Ok(f) => Ok(BufReader::new(f)),
+        Err(err) => Err(SWCParseError::Io {
+            file_name: file_name.to_string(),
+            message: err.to_string(),
+        }),
+    }
 }
 
-fn parse_lines(reader: BufReader<File>) -> Result<SWCNeuron, String> {
+/// Parse SWC lines into an `SWCNeuron`.
+///
+/// Compartments may appear in any order. Parents are resolved only after the
+/// whole file has been read, so a compartment may refer to a parent that is
+/// defined further down.
+fn parse_lines<R: BufRead>(
+    reader: R,
+    file_name: &str,
+    mode: ParseMode,
+) -> Result<(SWCNeuron, ParseReport), SWCParseError> {
     let mut neuron = SWCNeuron::new();
+    let mut report = ParseReport::new();
+
+    // Where each compartment was defined, for locating problems found after
+    // the whole file has been read.
+    let mut source_lines = BTreeMap::<usize, (usize, String)>::new();
+
+    for (line_index, line) in reader.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                return Err(SWCParseError::Io {
+                    file_name: file_name.to_string(),
+                    message: format!("could not read line {}: {}", line_number, err),
+                })
+            }
+        };
 
-    for line in reader.lines() {
-        match parse_line(line.expect("Could not read line."))? {
-            SWCLine::SWCCompartment(compartment) => neuron.try_insert(compartment)?,
-            SWCLine::Comment(_) => {},
+        let parsed_line = match (parse_line(line.clone()), mode) {
+            (Ok(parsed_line), _) => parsed_line,
+            (Err(err), ParseMode::Strict) => return Err(err.locate(file_name, line_number, &line)),
+            (Err(err), ParseMode::Lenient(_)) => {
+                // Lines that can't be parsed can't be repaired either.
+                report.push(ReportEntry {
+                    severity: Severity::Error,
+                    error: err.locate(file_name, line_number, &line),
+                    action: RepairAction::SkippedLine,
+                });
+                continue;
+            }
+        };
+
+        match parsed_line {
+            SWCLine::SWCCompartment(compartment) => match (neuron.try_insert(compartment), mode) {
+                (Ok(_), _) => {
+                    source_lines.insert(compartment.id, (line_number, line));
+                }
+                (Err(err), ParseMode::Strict) => {
+                    return Err(err.locate(file_name, line_number, &line))
+                }
+                (Err(err), ParseMode::Lenient(_)) => report.push(ReportEntry {
+                    severity: Severity::Error,
+                    error: err.locate(file_name, line_number, &line),
+                    action: RepairAction::SkippedLine,
+                }),
+            },
+            SWCLine::Comment(comment) => {
+                neuron.metadata.try_insert_from_comment(&comment);
+                neuron.comments.push(comment);
+            }
             SWCLine::Blank => {}
         }
     }
 
-    return Ok(neuron);
+    // Second pass: make sure every parent exists and that there are no cycles.
+    // Repairing or skipping one compartment can leave its children without a
+    // parent, so keep going until nothing is left to fix.
+    loop {
+        let mut problems = neuron.find_unresolved_parents();
+        if problems.is_empty() {
+            break;
+        }
+        problems.sort_by_key(|problem| source_lines[&problem.get_id()].0);
+
+        for problem in problems.drain(..) {
+            let (line_number, line) = &source_lines[&problem.get_id()];
+            let error = problem.into_error().locate(file_name, *line_number, line);
+            match mode {
+                ParseMode::Strict => return Err(error),
+                ParseMode::Lenient(policy) => report.push(neuron.repair(error, policy)),
+            }
+        }
+    }
+    report.sort();
+
+    return Ok((neuron, report));
+}
+
+/// How strictly to treat problems in an SWC file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Stop at the first problem.
+    Strict,
+    /// Record every problem and keep going.
+    Lenient(RepairPolicy),
+}
+
+/// What to do with compartment lines that can't be used as-is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RepairPolicy {
+    /// Leave out any line with a problem.
+    Skip,
+    /// Keep compartments whose parent can't be used by turning them into roots,
+    /// and leave out lines that can't be repaired.
+    Repair,
+}
+
+/// How serious a problem in an SWC file is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The line was left out of the neuron.
+    Error,
+    /// The line was changed before being added to the neuron.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// What was done about a problem in an SWC file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RepairAction {
+    /// The line was ignored.
+    SkippedLine,
+    /// The compartment was added as a root instead of being attached to its parent.
+    DetachedFromParent,
+}
+
+impl fmt::Display for RepairAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RepairAction::SkippedLine => write!(f, "line skipped"),
+            RepairAction::DetachedFromParent => write!(f, "compartment added as a new root"),
+        }
+    }
+}
+
+/// A problem found while leniently parsing an SWC file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReportEntry {
+    pub severity: Severity,
+    pub error: SWCParseError,
+    pub action: RepairAction,
+}
+
+impl ReportEntry {
+    /// Get a compiler-style description of the problem and what was done about it.
+    pub fn to_diagnostic(&self) -> String {
+        self.error
+            .render(&self.severity.to_string(), Some(&self.action.to_string()))
+    }
+}
+
+/// All problems found while leniently parsing an SWC file, in file order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParseReport {
+    entries: Vec<ReportEntry>,
+}
+
+impl ParseReport {
+    pub fn new() -> ParseReport {
+        ParseReport {
+            entries: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, entry: ReportEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Put entries in file order.
+    fn sort(&mut self) {
+        self.entries.sort_by_key(|entry| match entry.error.span() {
+            Some(span) => span.line_number,
+            None => 0,
+        });
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ReportEntry> {
+        self.entries.iter()
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.severity == severity)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod parse_lines_tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn parse_str(
+        name: &str,
+        contents: &str,
+        mode: ParseMode,
+    ) -> Result<(SWCNeuron, ParseReport), SWCParseError> {
+        parse_lines(Cursor::new(contents), name, mode)
+    }
+
+    #[test]
+    fn parents_may_come_after_children() {
+        let contents = "3 3 0 0 0 1 2\n2 3 0 0 0 1 1\n1 1 0 0 0 1 -1\n";
+        let (neuron, report) = parse_str("unordered", contents, ParseMode::Strict).unwrap();
+        assert!(report.is_empty());
+        assert_eq!(neuron.iter().count(), 3);
+    }
+
+    #[test]
+    fn missing_parent_is_error() {
+        let contents = "2 3 0 0 0 1 1\n3 3 0 0 0 1 4\n";
+        match parse_str("missing_parent", contents, ParseMode::Strict) {
+            Err(SWCParseError::MissingParent { span, .. }) => assert_eq!(span.line_number, 1),
+            _ => panic!("Expected parent 1 of compartment 2 to be reported missing"),
+        }
+    }
+
+    #[test]
+    fn cycle_is_error() {
+        let contents = "1 1 0 0 0 1 -1\n2 3 0 0 0 1 3\n3 3 0 0 0 1 4\n4 3 0 0 0 1 2\n";
+        match parse_str("cycle", contents, ParseMode::Strict) {
+            Err(SWCParseError::Cycle { id, cycle, .. }) => {
+                assert_eq!(id, 2);
+                assert_eq!(cycle, vec![2, 3, 4]);
+            }
+            _ => panic!("Expected cycle 2 -> 3 -> 4 -> 2 to be an error"),
+        }
+    }
+
+    #[test]
+    fn self_parent_is_cycle() {
+        let contents = "1 1 0 0 0 1 1\n";
+        match parse_str("self_parent", contents, ParseMode::Strict) {
+            Err(SWCParseError::Cycle { id, cycle, .. }) => {
+                assert_eq!(id, 1);
+                assert_eq!(cycle, vec![1]);
+            }
+            _ => panic!("Expected compartment that is its own parent to be an error"),
+        }
+    }
+
+    #[test]
+    fn repair_policy_breaks_cycles() {
+        let contents = "1 1 0 0 0 1 -1\n2 3 0 0 0 1 3\n3 3 0 0 0 1 2\n";
+        let (neuron, report) = parse_str(
+            "repair_cycle",
+            contents,
+            ParseMode::Lenient(RepairPolicy::Repair),
+        )
+        .unwrap();
+        assert_eq!(neuron.iter().count(), 3);
+        assert_eq!(report.warning_count(), 1);
+        assert_eq!(neuron.find_unresolved_parents().len(), 0);
+    }
+
+    #[test]
+    fn skip_policy_drops_whole_cycle() {
+        let contents = "1 1 0 0 0 1 -1\n2 3 0 0 0 1 3\n3 3 0 0 0 1 2\n4 3 0 0 0 1 3\n";
+        let (neuron, report) = parse_str(
+            "skip_cycle",
+            contents,
+            ParseMode::Lenient(RepairPolicy::Skip),
+        )
+        .unwrap();
+        let ids: Vec<usize> = neuron.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![1]);
+        assert_eq!(report.error_count(), 3);
+    }
+
+    static BROKEN_SWC: &str = "# header
+1 1 0 0 0 1 -1
+2 3 x 0 0 1 1
+3 3 0 0 0 1 2
+3 3 0 0 0 1 1
+4 3 0 0 0 1 1
+5 3 0 0 0
+";
+
+    #[test]
+    fn strict_mode_stops_at_first_error() {
+        match parse_str("strict", BROKEN_SWC, ParseMode::Strict) {
+            Err(SWCParseError::BadNumber { span, .. }) => assert_eq!(span.line_number, 3),
+            _ => panic!("Expected strict parsing to fail on line 3"),
+        }
+    }
+
+    #[test]
+    fn lenient_mode_reports_every_problem() {
+        let (_, report) = parse_str(
+            "lenient_all",
+            BROKEN_SWC,
+            ParseMode::Lenient(RepairPolicy::Repair),
+        )
+        .unwrap();
+        let lines: Vec<usize> = report
+            .iter()
+            .map(|entry| entry.error.span().unwrap().line_number)
+            .collect();
+        assert_eq!(lines, vec![3, 4, 5, 7]);
+    }
+
+    #[test]
+    fn skip_policy_leaves_out_dangling_compartments() {
+        let (neuron, report) = parse_str(
+            "lenient_skip",
+            BROKEN_SWC,
+            ParseMode::Lenient(RepairPolicy::Skip),
+        )
+        .unwrap();
+        let ids: Vec<usize> = neuron.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![1, 4]);
+        assert_eq!(report.error_count(), 4);
+        assert_eq!(report.warning_count(), 0);
+    }
+
+    #[test]
+    fn repair_policy_detaches_dangling_compartments() {
+        let (neuron, report) = parse_str(
+            "lenient_repair",
+            BROKEN_SWC,
+            ParseMode::Lenient(RepairPolicy::Repair),
+        )
+        .unwrap();
+        let ids: Vec<usize> = neuron.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![1, 3, 4]);
+        assert_eq!(neuron.iter().nth(1).unwrap().1.parent_id, None);
+        assert_eq!(report.error_count(), 3);
+        assert_eq!(report.warning_count(), 1);
+    }
 }
 
-fn parse_line(line: String) -> Result<SWCLine, String> {
-    let trimmed_line = line.trim();  // Remove leading and trailing whitespace.
+fn parse_line(line: String) -> Result<SWCLine, SWCParseError> {
+    let trimmed_line = line.trim(); // Remove leading and trailing whitespace.
 
     let parse_result: SWCLine;
     if trimmed_line.is_empty() {
@@ -43,14 +415,14 @@ fn parse_line(line: String) -> Result<SWCLine, String> {
     } else {
         // Line is not empty.
 
-        if trimmed_line.chars().next().unwrap() == '#' {
+        if trimmed_line.starts_with('#') {
             // Parse line as a comment, causing parse_result to be
             // SWCLine::Comment
             parse_result = SWCLine::Comment(trimmed_line.to_string());
         } else {
             // Parse line as a compartment, causing parse_result to be
             // SWCLine::SWCCompartment
-            parse_result = SWCLine::SWCCompartment(parse_line_as_compartment(trimmed_line.to_string())?);
+            parse_result = SWCLine::SWCCompartment(parse_line_as_compartment(line.clone())?);
         }
     }
 
@@ -65,6 +437,7 @@ enum SWCLine {
 }
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod parse_line_tests {
     use super::*;
 
@@ -114,61 +487,70 @@ mod parse_line_tests {
     }
 }
 
-fn parse_line_as_compartment(line: String) -> Result<SWCCompartment, String> {
+fn parse_line_as_compartment(line: String) -> Result<SWCCompartment, SWCParseError> {
     let specs: Vec<&str> = line.split_whitespace().collect();
 
     // Check number of space-delimited items.
     if specs.len() != 7 {
-        return Err(format!(
-            "Expected 7 space-delimited items in compartment line,
-                got {} items instead.",
-            specs.len()
-        ));
-    }
-
-    let id: usize;
-    match specs[0].parse::<usize>() {
-        Ok(parsed_id) => id = parsed_id,
-        Err(_) => return Err(format!("Could not parse {} as a compartment id.", specs[0])),
-    }
-    let compartment_kind = SWCCompartmentKind::from(
-        specs[1]
-            .parse::<usize>()
-            .expect("Could not parse compartmentkind"),
-    );
+        return Err(SWCParseError::FieldCount {
+            span: Span::unknown(),
+            found: specs.len(),
+        });
+    }
+
+    let id = parse_field::<usize>(specs[0], SWCField::Id)?;
+    let kind_code = parse_field::<usize>(specs[1], SWCField::Kind)?;
     let position = Point {
-        x: specs[2].parse::<f64>().expect("Could not parse x position"),
-        y: specs[3].parse::<f64>().expect("Could not parse y position"),
-        z: specs[4].parse::<f64>().expect("Could not parse z position"),
+        x: parse_real(specs[2], SWCField::X)?,
+        y: parse_real(specs[3], SWCField::Y)?,
+        z: parse_real(specs[4], SWCField::Z)?,
     };
-    let radius = specs[5].parse::<f64>().expect("Could not parse radius");
+    let radius = parse_real(specs[5], SWCField::Radius)?;
 
     let parent_id: Option<usize>;
-    if specs[6].chars().next().unwrap() == '-' {
+    let parsed_parent_id = parse_field::<i64>(specs[6], SWCField::Parent)?;
+    if parsed_parent_id < 0 {
         // Negative parent id means there is no parent; this is the root of the
         // neuron graph.
         parent_id = None;
     } else {
-        let parsed_parent_id = specs[6]
-            .parse::<usize>()
-            .expect(&format!("Could not parse parent id {}", specs[6]));
-        if parsed_parent_id >= id {
-            return Err(format!(
-                "Expected parent_id for compartment {} to be less than {},
-                    got {} instead.",
-                id, id, parsed_parent_id
-            ));
-        }
-        parent_id = Some(parsed_parent_id);
+        parent_id = Some(parsed_parent_id as usize);
     }
 
-    return Ok(SWCCompartment::new(
+    let mut compartment = SWCCompartment::new(
         id,
-        compartment_kind,
+        SWCCompartmentKind::from(kind_code),
         position,
         radius,
         parent_id,
-    ));
+    );
+    compartment.custom_code = kind_code;
+    return Ok(compartment);
+}
+
+/// Parse a single whitespace-delimited token from a compartment line.
+fn parse_field<T: FromStr>(token: &str, field: SWCField) -> Result<T, SWCParseError> {
+    match token.parse::<T>() {
+        Ok(value) => Ok(value),
+        Err(_) => Err(SWCParseError::BadNumber {
+            span: Span::unknown(),
+            field,
+            token: token.to_string(),
+        }),
+    }
+}
+
+/// Parse a position or radius, which must be a finite number.
+fn parse_real(token: &str, field: SWCField) -> Result<f64, SWCParseError> {
+    let value = parse_field::<f64>(token, field)?;
+    if !value.is_finite() {
+        return Err(SWCParseError::BadNumber {
+            span: Span::unknown(),
+            field,
+            token: token.to_string(),
+        });
+    }
+    return Ok(value);
 }
 
 #[cfg(test)]
@@ -177,6 +559,7 @@ mod parse_line_as_compartment_tests {
 
     /// An SWC line should have exactly seven space-delimited items. These
     /// tests ensure that lines are parsed as the correct length.
+    #[allow(clippy::assertions_on_constants)]
     mod line_length_tests {
         use super::*;
 
@@ -185,7 +568,7 @@ mod parse_line_as_compartment_tests {
             let line = "2 3 4 5 6 7 1 1".to_string();
             match parse_line_as_compartment(line) {
                 Ok(_) => assert!(false),
-                Err(msg) => assert!(msg.contains("got 8 items"))
+                Err(msg) => assert!(msg.to_string().contains("got 8 items")),
             }
         }
 
@@ -194,7 +577,7 @@ mod parse_line_as_compartment_tests {
             let line = "2 3 4 5 6 7".to_string();
             match parse_line_as_compartment(line) {
                 Ok(_) => assert!(false),
-                Err(msg) => assert!(msg.contains("got 6 items"))
+                Err(msg) => assert!(msg.to_string().contains("got 6 items")),
             }
         }
 
@@ -227,6 +610,7 @@ mod parse_line_as_compartment_tests {
     }
 
     #[cfg(test)]
+    #[allow(clippy::assertions_on_constants)]
     mod id {
         use super::*;
 
@@ -250,6 +634,13 @@ mod parse_line_as_compartment_tests {
             }
         }
 
+        #[test]
+        fn custom_kind_code_is_preserved() {
+            let swc_compartment = parse_line_as_compartment("10 7 3 3 3 1 6".to_string()).unwrap();
+            assert_eq!(swc_compartment.kind, SWCCompartmentKind::Custom);
+            assert_eq!(swc_compartment.get_kind_code(), 7);
+        }
+
         #[test]
         fn radius() {
             for rad in [4.3, 7.7, 9.9, 3.2].iter() {
@@ -285,50 +676,661 @@ mod parse_line_as_compartment_tests {
     }
 }
 
+/// Fields of a compartment line, in the order they appear in an SWC file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SWCField {
+    Id,
+    Kind,
+    X,
+    Y,
+    Z,
+    Radius,
+    Parent,
+}
+
+impl fmt::Display for SWCField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SWCField::Id => write!(f, "compartment id"),
+            SWCField::Kind => write!(f, "compartment kind"),
+            SWCField::X => write!(f, "x position"),
+            SWCField::Y => write!(f, "y position"),
+            SWCField::Z => write!(f, "z position"),
+            SWCField::Radius => write!(f, "radius"),
+            SWCField::Parent => write!(f, "parent id"),
+        }
+    }
+}
+
+/// Location of an offending token in an SWC file.
+///
+/// Line and column numbers start at 1. A `line_number` of 0 means that the
+/// location has not been filled in yet (see `SWCParseError::locate()`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub file_name: String,
+    pub line_number: usize,
+    pub column: usize,
+    pub length: usize,
+    pub line: String,
+}
+
+impl Span {
+    fn unknown() -> Span {
+        Span {
+            file_name: String::new(),
+            line_number: 0,
+            column: 0,
+            length: 0,
+            line: String::new(),
+        }
+    }
+
+    /// Get the span of a whitespace-delimited token in `line`.
+    ///
+    /// If `token_index` is `None` or the token does not exist, the span
+    /// covers the whole of the trimmed line.
+    fn of_token(
+        file_name: &str,
+        line_number: usize,
+        line: &str,
+        token_index: Option<usize>,
+    ) -> Span {
+        let mut tokens = Vec::with_capacity(7);
+        let mut token_start: Option<usize> = None;
+        for (position, character) in line.char_indices() {
+            match (character.is_whitespace(), token_start) {
+                (false, None) => token_start = Some(position),
+                (true, Some(start)) => {
+                    tokens.push((start, position));
+                    token_start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(start) = token_start {
+            tokens.push((start, line.len()));
+        }
+
+        let (start, end) = match token_index.and_then(|index| tokens.get(index)) {
+            Some(bounds) => *bounds,
+            None => match (tokens.first(), tokens.last()) {
+                (Some(first), Some(last)) => (first.0, last.1),
+                _ => (0, 0),
+            },
+        };
+
+        Span {
+            file_name: file_name.to_string(),
+            line_number,
+            column: line[..start].chars().count() + 1,
+            length: max(line[start..end].chars().count(), 1),
+            line: line.to_string(),
+        }
+    }
+
+    fn is_known(&self) -> bool {
+        self.line_number > 0
+    }
+}
+
+/// Errors that can occur while reading an SWC file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SWCParseError {
+    /// The file could not be opened or read.
+    Io { file_name: String, message: String },
+    /// A compartment line does not have exactly seven fields.
+    FieldCount { span: Span, found: usize },
+    /// A field could not be parsed as a number of the expected type.
+    BadNumber {
+        span: Span,
+        field: SWCField,
+        token: String,
+    },
+    /// More than one compartment has the same id.
+    DuplicateId { span: Span, id: usize },
+    /// A compartment refers to a parent that has not been defined.
+    MissingParent {
+        span: Span,
+        id: usize,
+        parent_id: usize,
+    },
+    /// A compartment is its own ancestor.
+    Cycle {
+        span: Span,
+        id: usize,
+        cycle: Vec<usize>,
+    },
+}
+
+impl SWCParseError {
+    /// Get the location of the error, if it refers to a specific line.
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            SWCParseError::Io { .. } => None,
+            SWCParseError::FieldCount { span, .. }
+            | SWCParseError::BadNumber { span, .. }
+            | SWCParseError::DuplicateId { span, .. }
+            | SWCParseError::MissingParent { span, .. }
+            | SWCParseError::Cycle { span, .. } => Some(span),
+        }
+    }
+
+    /// Description of the error without location information.
+    pub fn message(&self) -> String {
+        match self {
+            SWCParseError::Io { file_name, message } => {
+                format!("could not read {}: {}", file_name, message)
+            }
+            SWCParseError::FieldCount { found, .. } => format!(
+                "expected 7 space-delimited items in compartment line, got {} items instead",
+                found
+            ),
+            SWCParseError::BadNumber { field, token, .. } => {
+                format!("could not parse `{}` as {}", token, field)
+            }
+            SWCParseError::DuplicateId { id, .. } => {
+                format!("more than one compartment with id {} exists", id)
+            }
+            SWCParseError::MissingParent { id, parent_id, .. } => format!(
+                "parent {} of compartment {} does not exist",
+                parent_id, id
+            ),
+            SWCParseError::Cycle { id, cycle, .. } => format!(
+                "compartment {} is its own ancestor (cycle {} -> {})",
+                id,
+                cycle.iter().format(" -> "),
+                id
+            ),
+        }
+    }
+
+    /// Attach the file name and line on which the error occurred.
+    ///
+    /// The column is inferred from the field the error refers to.
+    fn locate(self, file_name: &str, line_number: usize, line: &str) -> SWCParseError {
+        let token_index = match &self {
+            SWCParseError::Io { .. } => return self,
+            SWCParseError::FieldCount { .. } => None,
+            SWCParseError::BadNumber { field, .. } => Some(*field as usize),
+            SWCParseError::DuplicateId { .. } => Some(SWCField::Id as usize),
+            SWCParseError::MissingParent { .. } | SWCParseError::Cycle { .. } => {
+                Some(SWCField::Parent as usize)
+            }
+        };
+        let located = Span::of_token(file_name, line_number, line, token_index);
+
+        let mut error = self;
+        match &mut error {
+            SWCParseError::Io { .. } => {}
+            SWCParseError::FieldCount { span, .. }
+            | SWCParseError::BadNumber { span, .. }
+            | SWCParseError::DuplicateId { span, .. }
+            | SWCParseError::MissingParent { span, .. }
+            | SWCParseError::Cycle { span, .. } => *span = located,
+        }
+        return error;
+    }
+
+    /// Get a compiler-style description of the error.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// error: could not parse `1.2.3` as x position
+    ///   --> cell.swc:14:5
+    ///    |
+    /// 14 | 12 3 1.2.3 4 5 6 11
+    ///    |      ^^^^^
+    /// ```
+    pub fn to_diagnostic(&self) -> String {
+        self.render("error", None)
+    }
+
+    /// Get a compiler-style description of the error with a custom label and
+    /// an optional note.
+    fn render(&self, label: &str, note: Option<&str>) -> String {
+        let mut diagnostic = format!("{}: {}", label, self.message());
+        if let Some(span) = self.span().filter(|span| span.is_known()) {
+            let gutter_width = span.line_number.to_string().len();
+            let gutter = " ".repeat(gutter_width);
+            diagnostic.push_str(&format!(
+                "\n{}--> {}:{}:{}",
+                gutter, span.file_name, span.line_number, span.column
+            ));
+            diagnostic.push_str(&format!("\n{} |", gutter));
+            diagnostic.push_str(&format!("\n{} | {}", span.line_number, span.line));
+            diagnostic.push_str(&format!(
+                "\n{} | {}{}",
+                gutter,
+                " ".repeat(span.column - 1),
+                "^".repeat(span.length)
+            ));
+            if let Some(note) = note {
+                diagnostic.push_str(&format!("\n{} = note: {}", gutter, note));
+            }
+        } else if let Some(note) = note {
+            diagnostic.push_str(&format!("\n  = note: {}", note));
+        }
+        return diagnostic;
+    }
+}
+
+impl fmt::Display for SWCParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.span().filter(|span| span.is_known()) {
+            Some(span) => write!(
+                f,
+                "{}:{}:{}: {}",
+                span.file_name,
+                span.line_number,
+                span.column,
+                self.message()
+            ),
+            None => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod swcparseerror_tests {
+    use super::*;
+
+    #[test]
+    fn bad_number_reports_field_and_token() {
+        let line = "12 3 1.2.3 4 5 6 11".to_string();
+        match parse_line_as_compartment(line) {
+            Ok(_) => panic!("Expected `1.2.3` to be rejected as an x position"),
+            Err(err) => assert_eq!(
+                err,
+                SWCParseError::BadNumber {
+                    span: Span::unknown(),
+                    field: SWCField::X,
+                    token: "1.2.3".to_string()
+                }
+            ),
+        }
+    }
+
+    #[test]
+    fn non_finite_numbers_are_error() {
+        for (line, field) in [
+            ("12 3 nan 4 5 6 11", SWCField::X),
+            ("12 3 1 inf 5 6 11", SWCField::Y),
+            ("12 3 1 4 -inf 6 11", SWCField::Z),
+            ("12 3 1 4 5 NaN 11", SWCField::Radius),
+        ]
+        .iter()
+        {
+            match parse_line_as_compartment(line.to_string()) {
+                Err(SWCParseError::BadNumber { field: bad_field, .. }) => {
+                    assert_eq!(bad_field, *field)
+                }
+                _ => panic!("Expected non-finite number in `{}` to be an error", line),
+            }
+        }
+    }
+
+    #[test]
+    fn bad_kind_is_error() {
+        let line = "12 soma 1 4 5 6 11".to_string();
+        match parse_line_as_compartment(line) {
+            Err(SWCParseError::BadNumber { field, .. }) => assert_eq!(field, SWCField::Kind),
+            _ => panic!("Expected non-numeric compartment kind to be an error"),
+        }
+    }
+
+    #[test]
+    fn locate_points_at_offending_token() {
+        let line = "12  3 1.2.3 4 5 6 11";
+        let err = parse_line_as_compartment(line.to_string())
+            .unwrap_err()
+            .locate("cell.swc", 14, line);
+        let span = err.span().unwrap();
+        assert_eq!(span.file_name, "cell.swc");
+        assert_eq!(span.line_number, 14);
+        assert_eq!(span.column, 7);
+        assert_eq!(span.length, 5);
+    }
+
+    #[test]
+    fn locate_parent_error_points_at_parent() {
+        let line = "3 3 0 0 0 1 7";
+        let err = SWCParseError::MissingParent {
+            span: Span::unknown(),
+            id: 3,
+            parent_id: 7,
+        }
+        .locate("cell.swc", 3, line);
+        assert_eq!(err.span().unwrap().column, 13);
+    }
+
+    #[test]
+    fn display_includes_location() {
+        let line = "12 3 1 4 5 6";
+        let err = parse_line_as_compartment(line.to_string())
+            .unwrap_err()
+            .locate("cell.swc", 2, line);
+        assert!(err.to_string().starts_with("cell.swc:2:1: "));
+    }
+
+    #[test]
+    fn diagnostic_underlines_token() {
+        let line = "12 3 1 4 5 x 11";
+        let err = parse_line_as_compartment(line.to_string())
+            .unwrap_err()
+            .locate("cell.swc", 14, line);
+        let diagnostic = err.to_diagnostic();
+        assert!(diagnostic.contains("--> cell.swc:14:12"));
+        assert!(diagnostic.ends_with("   |            ^"));
+    }
+
+}
+
 pub struct SWCNeuron {
     compartments: BTreeMap<usize, SWCCompartment>,
+    comments: Vec<String>,
+    metadata: Metadata,
 }
 
 impl SWCNeuron {
     fn new() -> SWCNeuron {
         SWCNeuron {
             compartments: BTreeMap::<usize, SWCCompartment>::new(),
+            comments: Vec::new(),
+            metadata: Metadata::new(),
+        }
+    }
+
+    /// Add a compartment to the neuron.
+    ///
+    /// The compartment's parent does not need to exist yet. Use
+    /// `find_unresolved_parents()` once all compartments have been inserted.
+    fn try_insert(&mut self, compartment: SWCCompartment) -> Result<(), SWCParseError> {
+        if self.compartments.contains_key(&compartment.id) {
+            return Err(SWCParseError::DuplicateId {
+                span: Span::unknown(),
+                id: compartment.id,
+            });
+        }
+
+        self.compartments.insert(compartment.id, compartment);
+        return Ok(());
+    }
+
+    /// Find compartments whose parent doesn't exist or that are their own ancestor.
+    ///
+    /// Each cycle is reported once, against the compartment in the cycle with
+    /// the smallest id.
+    fn find_unresolved_parents(&self) -> Vec<UnresolvedParent> {
+        let mut problems = Vec::new();
+
+        // Compartments known to lead to a root, or to a problem that has
+        // already been reported.
+        let mut resolved = BTreeSet::<usize>::new();
+
+        for (id, compartment) in self.compartments.iter() {
+            if resolved.contains(id) {
+                continue;
+            }
+
+            // Walk up towards the root until reaching a resolved compartment,
+            // a missing parent, or a compartment seen earlier on this walk.
+            let mut path = vec![*id];
+            let mut current = compartment;
+            while let Some(parent_id) = current.parent_id {
+                if resolved.contains(&parent_id) {
+                    break;
+                }
+                if let Some(position) = path.iter().position(|visited| *visited == parent_id) {
+                    let cycle = path[position..].to_vec();
+                    let smallest = *cycle.iter().min().unwrap();
+                    let start = cycle.iter().position(|member| *member == smallest).unwrap();
+                    // Rotate the cycle so that it starts at `smallest` and
+                    // follows parent links.
+                    let mut ordered = cycle[start..].to_vec();
+                    ordered.extend_from_slice(&cycle[..start]);
+                    problems.push(UnresolvedParent::Cycle(smallest, ordered));
+                    break;
+                }
+                match self.compartments.get(&parent_id) {
+                    Some(parent) => {
+                        path.push(parent_id);
+                        current = parent;
+                    }
+                    None => {
+                        problems.push(UnresolvedParent::Missing(current.id, parent_id));
+                        break;
+                    }
+                }
+            }
+
+            resolved.extend(path);
         }
+
+        return problems;
     }
 
-    fn try_insert(&mut self, compartment: SWCCompartment) -> Result<(), String> {
-        match self.compartments.entry(compartment.id) {
-            Entry::Occupied(_) => Err(format!(
-                "More than one compartment with id {} exists",
-                compartment.id
-            )),
-            Entry::Vacant(entry) => {
-                entry.insert(compartment);
-                Ok(())
+    /// Fix or remove the compartment that `error` refers to according to `policy`.
+    ///
+    /// Returns a description of the problem and what was done about it.
+    #[allow(clippy::expect_fun_call)]
+    fn repair(&mut self, error: SWCParseError, policy: RepairPolicy) -> ReportEntry {
+        let id = match &error {
+            SWCParseError::MissingParent { id, .. } | SWCParseError::Cycle { id, .. } => *id,
+            _ => panic!(
+                "Only problems with parents can be repaired, got `{}`",
+                error
+            ),
+        };
+
+        match policy {
+            RepairPolicy::Repair => {
+                self.compartments
+                    .get_mut(&id)
+                    .expect(&format!("Could not find compartment {} to repair", id))
+                    .parent_id = None;
+                ReportEntry {
+                    severity: Severity::Warning,
+                    error,
+                    action: RepairAction::DetachedFromParent,
+                }
+            }
+            RepairPolicy::Skip => {
+                self.compartments.remove(&id);
+                ReportEntry {
+                    severity: Severity::Error,
+                    error,
+                    action: RepairAction::SkippedLine,
+                }
             }
         }
     }
 
-    pub fn iter(&self) -> Iter<usize, SWCCompartment> {
+    pub fn iter(&self) -> Iter<'_, usize, SWCCompartment> {
         self.compartments.iter()
     }
+
+    /// Get the comment lines of the SWC file, including the leading `#`, in file order.
+    pub fn get_comments(&self) -> &Vec<String> {
+        &self.comments
+    }
+
+    /// Get the well-known `# KEY value` entries found in the comments.
+    pub fn get_metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+}
+
+/// Keys of `# KEY value` comments written by NeuroMorpho.org, the Allen
+/// Institute and common reconstruction software.
+static METADATA_KEYS: &'static [&'static str] = &[
+    "ORIGINAL_SOURCE",
+    "CREATURE",
+    "SPECIES",
+    "REGION",
+    "FIELD/LAYER",
+    "TYPE",
+    "CONTRIBUTOR",
+    "CREATOR",
+    "REFERENCE",
+    "RAW",
+    "EXTRAS",
+    "SOMA_AREA",
+    "SHRINKAGE_CORRECTION",
+    "VERSION_NUMBER",
+    "VERSION_DATE",
+    "SCALE",
+    "UNITS",
+];
+
+/// Well-known metadata from the header of an SWC file, in file order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metadata {
+    entries: LinkedHashMap<String, String>,
+}
+
+impl Metadata {
+    pub fn new() -> Metadata {
+        Metadata {
+            entries: LinkedHashMap::new(),
+        }
+    }
+
+    /// Record the entry in a comment line of the form `# KEY value`.
+    ///
+    /// Keys are matched case-insensitively against a list of well-known keys
+    /// and stored in upper case. Other comments are ignored. If a key appears
+    /// more than once, the last value wins.
+    fn try_insert_from_comment(&mut self, comment: &str) {
+        let contents = comment.trim_start_matches('#').trim();
+        let (key, value) = match contents.find(char::is_whitespace) {
+            Some(position) => (&contents[..position], contents[position..].trim()),
+            None => (contents, ""),
+        };
+        let key = key.trim_end_matches(':').to_uppercase();
+        if METADATA_KEYS.contains(&key.as_str()) {
+            self.entries.insert(key, value.to_string());
+        }
+    }
+
+    pub fn iter(&self) -> linked_hash_map::Iter<'_, String, String> {
+        self.entries.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod metadata_tests {
+    use super::*;
+
+    impl Metadata {
+        fn get(&self, key: &str) -> Option<&String> {
+            self.entries.get(key)
+        }
+    }
+
+    #[test]
+    fn well_known_key() {
+        let mut metadata = Metadata::new();
+        metadata.try_insert_from_comment("# CREATURE rat");
+        assert_eq!(metadata.get("CREATURE"), Some(&"rat".to_string()));
+    }
+
+    #[test]
+    fn value_with_spaces() {
+        let mut metadata = Metadata::new();
+        metadata.try_insert_from_comment("#SHRINKAGE_CORRECTION 1.0 1.0 2.5");
+        assert_eq!(
+            metadata.get("SHRINKAGE_CORRECTION"),
+            Some(&"1.0 1.0 2.5".to_string())
+        );
+    }
+
+    #[test]
+    fn lowercase_key_with_colon() {
+        let mut metadata = Metadata::new();
+        metadata.try_insert_from_comment("# original_source: Neurolucida");
+        assert_eq!(
+            metadata.get("ORIGINAL_SOURCE"),
+            Some(&"Neurolucida".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_key_is_ignored() {
+        let mut metadata = Metadata::new();
+        metadata.try_insert_from_comment("# This file was converted by hand");
+        metadata.try_insert_from_comment("#");
+        assert!(metadata.is_empty());
+    }
+
+    #[test]
+    fn entries_keep_file_order() {
+        let mut metadata = Metadata::new();
+        metadata.try_insert_from_comment("# REGION neocortex");
+        metadata.try_insert_from_comment("# CREATURE mouse");
+        let keys: Vec<&String> = metadata.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec!["REGION", "CREATURE"]);
+    }
+}
+
+/// A compartment whose parent can't be resolved, found by
+/// `SWCNeuron::find_unresolved_parents()`.
+#[derive(Clone, Debug, PartialEq)]
+enum UnresolvedParent {
+    /// `Missing(id, parent_id)`
+    Missing(usize, usize),
+    /// `Cycle(id, cycle)`
+    Cycle(usize, Vec<usize>),
+}
+
+impl UnresolvedParent {
+    fn get_id(&self) -> usize {
+        match self {
+            UnresolvedParent::Missing(id, _) | UnresolvedParent::Cycle(id, _) => *id,
+        }
+    }
+
+    fn into_error(self) -> SWCParseError {
+        match self {
+            UnresolvedParent::Missing(id, parent_id) => SWCParseError::MissingParent {
+                span: Span::unknown(),
+                id,
+                parent_id,
+            },
+            UnresolvedParent::Cycle(id, cycle) => SWCParseError::Cycle {
+                span: Span::unknown(),
+                id,
+                cycle,
+            },
+        }
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod swcneuron_tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::expect_fun_call)]
     fn insert_compartments_with_unique_ids() {
         // Create a neuron and insert a single root compartment.
         let mut neuron = SWCNeuron::new();
         let mut compartment = SWCCompartment::new(0, SWCCompartmentKind::Soma, Point{x: 0.0, y: 0.0, z: 0.0}, 0.5, None);
-        neuron.try_insert(compartment.clone()).expect("Could not insert root node.");
+        neuron.try_insert(compartment).expect("Could not insert root node.");
 
         for compartment_id in [2, 5, 4, 7, 88, 903].iter() {
             compartment.parent_id = Some(0);
             compartment.id = *compartment_id;
-            neuron.try_insert(compartment.clone()).expect(&format!("Could not insert compartment with unique id {}", compartment_id));
+            neuron.try_insert(compartment).expect(&format!(
+                "Could not insert compartment with unique id {}",
+                compartment_id
+            ));
         }
     }
 
@@ -337,7 +1339,7 @@ mod swcneuron_tests {
         // Create a neuron and insert a single root compartment.
         let mut neuron = SWCNeuron::new();
         let mut compartment = SWCCompartment::new(1, SWCCompartmentKind::Soma, Point{x: 0.0, y: 0.0, z: 0.0}, 0.5, None);
-        neuron.try_insert(compartment.clone()).expect("Could not insert root node.");
+        neuron.try_insert(compartment).expect("Could not insert root node.");
 
         // Change all compartment attributes except id.
         compartment.radius += 1.0;
@@ -348,9 +1350,9 @@ mod swcneuron_tests {
         compartment.parent_id = Some(0);
 
         // Since id is still the same, inserting compartment again is an error.
-        match neuron.try_insert(compartment.clone()) {
+        match neuron.try_insert(compartment) {
             Ok(_) => assert!(false, "Inserting compartments with the same id should be an error"),
-            Err(msg) => assert!(msg.to_lowercase().contains("more than one compartment with id 1"))
+            Err(msg) => assert!(msg.to_string().to_lowercase().contains("more than one compartment with id 1"))
         }
     }
 }
@@ -359,6 +1361,10 @@ mod swcneuron_tests {
 pub struct SWCCompartment {
     pub id: usize,
     pub kind: SWCCompartmentKind,
+    /// SWC code that `kind` was parsed from.
+    ///
+    /// Only used to tell apart different `SWCCompartmentKind::Custom` codes.
+    pub custom_code: usize,
     pub position: Point,
     pub radius: f64,
     pub parent_id: Option<usize>,
@@ -375,11 +1381,22 @@ impl SWCCompartment {
         SWCCompartment {
             id: id,
             kind: kind,
+            custom_code: usize::from(kind),
             position: position,
             radius: radius,
             parent_id: parent_id,
         }
     }
+
+    /// Get the SWC code of the compartment kind.
+    ///
+    /// Codes of custom compartments are preserved as parsed.
+    pub fn get_kind_code(&self) -> usize {
+        match self.kind {
+            SWCCompartmentKind::Custom => self.custom_code,
+            kind => usize::from(kind),
+        }
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -390,7 +1407,9 @@ pub struct Point {
 }
 
 /// Types of compartment defined by the most basic version of the SWC standard.
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+///
+/// Variants are ordered by their SWC type code.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum SWCCompartmentKind {
     Undefined,
     Soma,
@@ -400,7 +1419,6 @@ pub enum SWCCompartmentKind {
     Custom,
 }
 
-use std::fmt;
 impl fmt::Display for SWCCompartmentKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -418,6 +1436,19 @@ impl SWCCompartmentKind {
     pub fn iter() -> SWCCompartmentKindIterator {
         SWCCompartmentKindIterator::new()
     }
+
+    /// Get the short name of a kind used in config files and data output,
+    /// eg `apicaldendrite`.
+    pub fn get_name(&self) -> &'static str {
+        match *self {
+            SWCCompartmentKind::Undefined => "undefined",
+            SWCCompartmentKind::Soma => "soma",
+            SWCCompartmentKind::Axon => "axon",
+            SWCCompartmentKind::Dendrite => "dendrite",
+            SWCCompartmentKind::ApicalDendrite => "apicaldendrite",
+            SWCCompartmentKind::Custom => "custom",
+        }
+    }
 }
 
 impl From<usize> for SWCCompartmentKind {
@@ -434,6 +1465,22 @@ impl From<usize> for SWCCompartmentKind {
     }
 }
 
+impl From<SWCCompartmentKind> for usize {
+    /// Get the SWC code of a compartment kind.
+    ///
+    /// `SWCCompartmentKind::Custom` is mapped to 5, the first custom code.
+    fn from(kind: SWCCompartmentKind) -> usize {
+        match kind {
+            SWCCompartmentKind::Undefined => 0,
+            SWCCompartmentKind::Soma => 1,
+            SWCCompartmentKind::Axon => 2,
+            SWCCompartmentKind::Dendrite => 3,
+            SWCCompartmentKind::ApicalDendrite => 4,
+            SWCCompartmentKind::Custom => 5,
+        }
+    }
+}
+
 impl IntoIterator for SWCCompartmentKind {
     type Item = SWCCompartmentKind;
     type IntoIter = SWCCompartmentKindIterator;
@@ -465,7 +1512,7 @@ impl SWCCompartmentKindIterator {
     }
 }
 
-/// Iterate over variants of `SWCCompartmentKind` in no particular order.
+/// Iterate over variants of `SWCCompartmentKind` in order of SWC type code.
 impl Iterator for SWCCompartmentKindIterator {
     type Item = SWCCompartmentKind;
 
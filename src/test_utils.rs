@@ -0,0 +1,52 @@
+use std::io::Cursor;
+
+use crate::components::Graph;
+use crate::config::Config;
+use crate::swc_parser::{parse_reader_with_mode, ParseMode, SWCNeuron};
+
+/// Parse the contents of an SWC file strictly.
+///
+/// # Panics
+///
+/// Panics if `swc` can't be parsed.
+pub fn parse_swc(swc: &str) -> SWCNeuron {
+    match parse_reader_with_mode(Cursor::new(swc), "test.swc", ParseMode::Strict) {
+        Ok((neuron, _)) => neuron,
+        Err(err) => panic!("Could not parse test SWC: {}", err.to_diagnostic()),
+    }
+}
+
+/// Get the graph of the contents of an SWC file (see `parse_swc()`).
+pub fn graph_from_swc(swc: &str) -> Graph {
+    Graph::from(parse_swc(swc))
+}
+
+/// Get the graph of a small neuron: a soma with a dendrite of two segments
+/// and an axon of one.
+///
+/// ```text
+/// 1 soma (0, 0, 0) r=2
+/// ├── 2 dendrite (0, 3, 0) r=1
+/// │   └── 3 dendrite (4, 3, 0) r=0.5
+/// └── 4 axon (0, -4, 0) r=1
+/// ```
+pub fn get_test_graph() -> Graph {
+    graph_from_swc(
+        "1 1 0 0 0 2 -1\n\
+         2 3 0 3 0 1 1\n\
+         3 3 4 3 0 0.5 2\n\
+         4 2 0 -4 0 1 1\n",
+    )
+}
+
+/// Get the default configuration.
+///
+/// # Panics
+///
+/// Panics if the default configuration can't be loaded.
+pub fn get_test_config() -> Config {
+    match Config::new() {
+        Ok(config) => config,
+        Err(err) => panic!("Could not construct default Config: {}", err),
+    }
+}
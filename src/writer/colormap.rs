@@ -0,0 +1,270 @@
+#![allow(
+    clippy::needless_return,
+    clippy::redundant_field_names,
+    clippy::redundant_static_lifetimes
+)]
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::components::Graph;
+
+/// A continuous color scale for mapping numbers onto colors.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Colormap {
+    Viridis,
+    Magma,
+    Plasma,
+    Inferno,
+    Coolwarm,
+    Greys,
+}
+
+/// Names accepted by `Colormap::from_str()`.
+pub static COLORMAP_NAMES: &'static [&'static str] =
+    &["viridis", "magma", "plasma", "inferno", "coolwarm", "greys"];
+
+// Evenly spaced samples of each colormap, from 0 to 1.
+static VIRIDIS: &'static [u32] = &[
+    0x440154, 0x482475, 0x414487, 0x355f8d, 0x2a788e, 0x21918c, 0x22a884, 0x44bf70, 0x7ad151,
+    0xbddf26, 0xfde725,
+];
+static MAGMA: &'static [u32] = &[
+    0x000004, 0x140e36, 0x3b0f70, 0x641a80, 0x8c2981, 0xb73779, 0xde4968, 0xf7705c, 0xfe9f6d,
+    0xfecf92, 0xfcfdbf,
+];
+static PLASMA: &'static [u32] = &[
+    0x0d0887, 0x41049d, 0x6a00a8, 0x8f0da4, 0xb12a90, 0xcc4778, 0xe16462, 0xf2844b, 0xfca636,
+    0xfcce25, 0xf0f921,
+];
+static INFERNO: &'static [u32] = &[
+    0x000004, 0x160b39, 0x420a68, 0x6a176e, 0x932667, 0xbc3754, 0xdd513a, 0xf37819, 0xfca50a,
+    0xf6d746, 0xfcffa4,
+];
+static COOLWARM: &'static [u32] = &[0x3b4cc0, 0x7b9ff9, 0xdddcdc, 0xf49a7b, 0xb40426];
+static GREYS: &'static [u32] = &[0xffffff, 0x000000];
+
+impl Colormap {
+    /// Get the color at a position between 0 and 1 as `#rrggbb`.
+    ///
+    /// Positions outside of `[0, 1]` get the color at the nearest end.
+    pub fn get_color(&self, position: f64) -> String {
+        let stops = self.get_stops();
+        let position = if position.is_nan() {
+            0.0
+        } else {
+            position.clamp(0.0, 1.0)
+        };
+        let scaled = position * (stops.len() - 1) as f64;
+        let lower = (scaled.floor() as usize).min(stops.len() - 2);
+        let fraction = scaled - lower as f64;
+
+        let mut color = String::from("#");
+        for shift in [16, 8, 0].iter() {
+            let start = ((stops[lower] >> shift) & 0xff) as f64;
+            let end = ((stops[lower + 1] >> shift) & 0xff) as f64;
+            let channel = (start + fraction * (end - start)).round() as u8;
+            color.push_str(&format!("{:02x}", channel));
+        }
+        return color;
+    }
+
+    fn get_stops(&self) -> &'static [u32] {
+        match self {
+            Colormap::Viridis => VIRIDIS,
+            Colormap::Magma => MAGMA,
+            Colormap::Plasma => PLASMA,
+            Colormap::Inferno => INFERNO,
+            Colormap::Coolwarm => COOLWARM,
+            Colormap::Greys => GREYS,
+        }
+    }
+}
+
+impl FromStr for Colormap {
+    type Err = ColormapParseError;
+
+    fn from_str(s: &str) -> Result<Colormap, ColormapParseError> {
+        match s {
+            "viridis" => Ok(Colormap::Viridis),
+            "magma" => Ok(Colormap::Magma),
+            "plasma" => Ok(Colormap::Plasma),
+            "inferno" => Ok(Colormap::Inferno),
+            "coolwarm" => Ok(Colormap::Coolwarm),
+            "greys" => Ok(Colormap::Greys),
+            _ => Err(ColormapParseError(s.to_string())),
+        }
+    }
+}
+
+/// A colormap name that is not one of `COLORMAP_NAMES`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColormapParseError(String);
+
+impl fmt::Display for ColormapParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expected one of {}, got `{}`",
+            COLORMAP_NAMES.join(", "),
+            self.0
+        )
+    }
+}
+
+/// Graphviz (X11) color names that are common in configuration files.
+static NAMED_COLORS: &'static [(&'static str, u32)] = &[
+    ("black", 0x000000),
+    ("blue", 0x0000ff),
+    ("brown", 0xa52a2a),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkgreen", 0x006400),
+    ("gold", 0xffd700),
+    ("gray", 0xbebebe),
+    ("green", 0x00ff00),
+    ("grey", 0xbebebe),
+    ("lightblue", 0xadd8e6),
+    ("lightgrey", 0xd3d3d3),
+    ("magenta", 0xff00ff),
+    ("navy", 0x000080),
+    ("orange", 0xffa500),
+    ("pink", 0xffc0cb),
+    ("purple", 0xa020f0),
+    ("red", 0xff0000),
+    ("violet", 0xee82ee),
+    ("white", 0xffffff),
+    ("yellow", 0xffff00),
+];
+
+/// Get the red, green and blue channels of a `#rrggbb` color or a common
+/// Graphviz color name.
+///
+/// Any alpha channel (`#rrggbbaa`) is ignored. Returns `None` for colors that
+/// can't be understood, such as color lists or uncommon names.
+pub fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let color = color.trim().to_lowercase();
+    let value = match color.strip_prefix('#') {
+        Some(hex) if (hex.len() == 6 || hex.len() == 8) && hex.is_ascii() => {
+            u32::from_str_radix(&hex[..6], 16).ok()?
+        }
+        Some(_) => return None,
+        None => {
+            NAMED_COLORS
+                .iter()
+                .find(|(name, _)| *name == color)?
+                .1
+        }
+    };
+    return Some([(value >> 16) as u8, (value >> 8) as u8, value as u8]);
+}
+
+/// Mapping from the values of a metric onto the colors of a colormap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorScale {
+    colormap: Colormap,
+    /// Values at the two ends of the colormap. The smallest and largest
+    /// values are used if this is not set.
+    range: Option<(f64, f64)>,
+}
+
+impl ColorScale {
+    pub fn new(colormap: Colormap) -> ColorScale {
+        ColorScale {
+            colormap: colormap,
+            range: None,
+        }
+    }
+
+    /// Set the values at the two ends of the colormap.
+    pub fn with_range(mut self, min: f64, max: f64) -> ColorScale {
+        self.range = Some((min, max));
+        return self;
+    }
+
+    /// Fill each vertex with the color of its value.
+    pub fn add_vertex_colors(&self, graph: &mut Graph, values: &BTreeMap<usize, f64>) {
+        let (min, max) = match self.range {
+            Some(range) => range,
+            None => values
+                .values()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                    (min.min(*value), max.max(*value))
+                }),
+        };
+        for (id, value) in values.iter() {
+            // Everything gets the middle color if all values are the same.
+            let position = if max > min {
+                (value - min) / (max - min)
+            } else {
+                0.5
+            };
+            graph.set_vertex_attribute(*id, "style", "filled".to_string());
+            graph.set_vertex_attribute(*id, "fillcolor", self.colormap.get_color(position));
+        }
+    }
+}
+
+#[cfg(test)]
+mod colormap_tests {
+    use super::*;
+    use crate::test_utils::graph_from_swc;
+
+    #[test]
+    fn ends_match_stops() {
+        assert_eq!(Colormap::Viridis.get_color(0.0), "#440154");
+        assert_eq!(Colormap::Viridis.get_color(1.0), "#fde725");
+    }
+
+    #[test]
+    fn interpolates_between_stops() {
+        assert_eq!(Colormap::Greys.get_color(0.5), "#808080");
+    }
+
+    #[test]
+    fn out_of_range_positions_are_clamped() {
+        assert_eq!(Colormap::Coolwarm.get_color(-1.0), "#3b4cc0");
+        assert_eq!(Colormap::Coolwarm.get_color(2.0), "#b40426");
+    }
+
+    #[test]
+    fn parse_colormap_names() {
+        for name in COLORMAP_NAMES.iter() {
+            assert!(name.parse::<Colormap>().is_ok(), "Could not parse {}", name);
+        }
+        assert!("jet".parse::<Colormap>().is_err());
+    }
+
+    #[test]
+    fn parse_hex_and_named_colors() {
+        assert_eq!(parse_color("#22a884"), Some([0x22, 0xa8, 0x84]));
+        assert_eq!(parse_color("#22A88480"), Some([0x22, 0xa8, 0x84]));
+        assert_eq!(parse_color("Green"), Some([0, 255, 0]));
+        assert_eq!(parse_color("grey"), Some([0xbe, 0xbe, 0xbe]));
+        assert_eq!(parse_color("red:blue"), None);
+        assert_eq!(parse_color("#12345"), None);
+    }
+
+    #[test]
+    fn vertices_are_filled_by_value() {
+        let swc = "1 1 0 0 0 1 -1\n2 3 0 1 0 1 1\n3 3 0 2 0 1 2\n";
+        let mut graph = graph_from_swc(swc);
+        let values: BTreeMap<usize, f64> =
+            vec![(1, 0.0), (2, 5.0), (3, 20.0)].into_iter().collect();
+        ColorScale::new(Colormap::Greys)
+            .with_range(0.0, 10.0)
+            .add_vertex_colors(&mut graph, &values);
+        let get_fillcolor = |id| {
+            graph
+                .get_vertex(id)
+                .unwrap()
+                .get_attributes()
+                .get("fillcolor")
+                .cloned()
+        };
+        assert_eq!(get_fillcolor(1), Some("#ffffff".to_string()));
+        assert_eq!(get_fillcolor(2), Some("#808080".to_string()));
+        assert_eq!(get_fillcolor(3), Some("#000000".to_string()));
+    }
+}
@@ -0,0 +1,36 @@
+#![allow(clippy::needless_return)]
+
+/// Quote a CSV field if it contains a comma, quote or line break.
+pub fn quote_csv(field: &str) -> String {
+    return quote_delimited(field, ",");
+}
+
+/// Quote a field of a delimited table if it contains the delimiter, a quote
+/// or a line break.
+pub fn quote_delimited(field: &str, delimiter: &str) -> String {
+    if field.contains(delimiter) || field.contains(['"', '\n', '\r']) {
+        return format!("\"{}\"", field.replace('"', "\"\""));
+    }
+    return field.to_string();
+}
+
+#[cfg(test)]
+mod csv_tests {
+    use super::*;
+
+    #[test]
+    fn plain_fields_are_not_quoted() {
+        assert_eq!(quote_csv("cells/a.swc"), "cells/a.swc");
+    }
+
+    #[test]
+    fn fields_with_separators_are_quoted() {
+        assert_eq!(quote_csv("a, \"b\""), "\"a, \"\"b\"\"\"");
+    }
+
+    #[test]
+    fn fields_with_other_delimiters_are_quoted() {
+        assert_eq!(quote_delimited("a, b", "\t"), "a, b");
+        assert_eq!(quote_delimited("a\tb", "\t"), "\"a\tb\"");
+    }
+}
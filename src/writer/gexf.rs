@@ -0,0 +1,345 @@
+#![allow(clippy::needless_return)]
+
+use crate::components::{Attributes, Graph};
+use crate::config::Config;
+use crate::swc_parser::SWCCompartmentKind;
+use crate::writer::colormap::parse_color;
+use crate::writer::format_number;
+use crate::writer::graphml::get_attribute_keys;
+use crate::writer::xml::escape_xml;
+use crate::writer::{get_edge_attributes_with_chain, EdgeDirection, Projection};
+
+/// Get a configured `String` representation of an object in GEXF format.
+///
+/// # See also
+///
+/// - `ConfiguredToDot` trait
+pub trait ConfiguredToGEXF {
+    fn to_gexf(&self, config: &Config) -> String;
+}
+
+impl ConfiguredToGEXF for Graph {
+    /// Get a GEXF document with a node per vertex and an edge per parent-child
+    /// pair, laid out and styled for Gephi.
+    ///
+    /// Nodes are placed at their projected position (onto the xy plane unless
+    /// `config` has a projection) and sized by radius, using the radius scale
+    /// of `config` if it has one. Nodes are colored by their `fillcolor` or
+    /// `color` attribute, taken from the vertex itself, the order groups of
+    /// `config` or the configuration of its kind, in that order.
+    ///
+    /// Attributes set on vertices and edges are written as well, except for a
+    /// vertex attribute called `kind`, which would clash with the kind of the
+    /// compartment.
+    fn to_gexf(&self, config: &Config) -> String {
+        let vertex_keys: Vec<(String, &'static str)> = get_attribute_keys(
+            self.iter_vertices()
+                .map(|(_, vertex)| vertex.get_attributes()),
+        )
+        .into_iter()
+        .filter(|(name, _)| name != "kind")
+        .collect();
+        let edge_attributes: Vec<(usize, usize, Attributes)> = self
+            .iter_edges()
+            .map(|(parent_id, child_id)| {
+                let attributes = get_edge_attributes_with_chain(self, parent_id, child_id);
+                (parent_id, child_id, attributes)
+            })
+            .collect();
+        let edge_keys = get_attribute_keys(
+            edge_attributes
+                .iter()
+                .map(|(_, _, attributes)| attributes),
+        );
+        let default_projection = Projection::xy();
+        let projection = config.get_projection().unwrap_or(&default_projection);
+        let order_attributes = config.get_order_attributes(self);
+
+        let mut gexf = String::with_capacity(256 * self.len());
+        gexf.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        gexf.push_str(
+            "<gexf xmlns=\"http://www.gexf.net/1.2draft\" \
+             xmlns:viz=\"http://www.gexf.net/1.2draft/viz\" version=\"1.2\">\n",
+        );
+        gexf.push_str("  <meta>\n    <creator>swc2dot</creator>\n  </meta>\n");
+        let edge_type = match config.get_edge_direction() {
+            EdgeDirection::Undirected => "undirected",
+            _ => "directed",
+        };
+        gexf.push_str(&format!(
+            "  <graph mode=\"static\" defaultedgetype=\"{}\">\n",
+            edge_type
+        ));
+        gexf.push_str("    <attributes class=\"node\">\n");
+        gexf.push_str(&attribute_to_gexf("kind", "string"));
+        for (name, attribute_type) in vertex_keys.iter() {
+            gexf.push_str(&attribute_to_gexf(name, attribute_type));
+        }
+        gexf.push_str("    </attributes>\n");
+        if !edge_keys.is_empty() {
+            gexf.push_str("    <attributes class=\"edge\">\n");
+            for (name, attribute_type) in edge_keys.iter() {
+                gexf.push_str(&attribute_to_gexf(name, attribute_type));
+            }
+            gexf.push_str("    </attributes>\n");
+        }
+
+        gexf.push_str("    <nodes>\n");
+        for (id, vertex) in self.iter_vertices() {
+            gexf.push_str(&format!("      <node id=\"{}\" label=\"{}\">\n", id, id));
+            gexf.push_str("        <attvalues>\n");
+            gexf.push_str(&attvalue_to_gexf("kind", vertex.get_kind().get_name()));
+            for (key, value) in vertex.get_attributes().iter() {
+                if key != "kind" {
+                    gexf.push_str(&attvalue_to_gexf(key, value));
+                }
+            }
+            gexf.push_str("        </attvalues>\n");
+
+            let size = match config.get_radius_scale() {
+                // Radius scales give diameters.
+                Some(radius_scale) => radius_scale.get_size(vertex.get_radius()) / 2.0,
+                None => vertex.get_radius() * projection.get_scale(),
+            };
+            gexf.push_str(&format!(
+                "        <viz:size value=\"{}\"/>\n",
+                format_number(size, 3)
+            ));
+            let (x, y) = projection.project(&vertex.get_position());
+            gexf.push_str(&format!(
+                "        <viz:position x=\"{}\" y=\"{}\" z=\"0\"/>\n",
+                format_number(x, 3),
+                format_number(y, 3)
+            ));
+            let color = get_vertex_color(
+                vertex.get_kind(),
+                vertex.get_attributes(),
+                order_attributes.get(id),
+                config,
+            );
+            if let Some([r, g, b]) = color {
+                gexf.push_str(&format!(
+                    "        <viz:color r=\"{}\" g=\"{}\" b=\"{}\"/>\n",
+                    r, g, b
+                ));
+            }
+            gexf.push_str("      </node>\n");
+        }
+        gexf.push_str("    </nodes>\n");
+
+        gexf.push_str("    <edges>\n");
+        for (i, (parent_id, child_id, attributes)) in edge_attributes.iter().enumerate() {
+            let (source_id, target_id) = match config.get_edge_direction() {
+                EdgeDirection::ChildToParent => (child_id, parent_id),
+                _ => (parent_id, child_id),
+            };
+            let edge = format!(
+                "      <edge id=\"{}\" source=\"{}\" target=\"{}\"",
+                i, source_id, target_id
+            );
+            if attributes.is_empty() {
+                gexf.push_str(&format!("{}/>\n", edge));
+            } else {
+                gexf.push_str(&format!("{}>\n        <attvalues>\n", edge));
+                for (key, value) in attributes.iter() {
+                    gexf.push_str(&attvalue_to_gexf(key, value));
+                }
+                gexf.push_str("        </attvalues>\n      </edge>\n");
+            }
+        }
+        gexf.push_str("    </edges>\n");
+        gexf.push_str("  </graph>\n</gexf>\n");
+        return gexf;
+    }
+}
+
+/// Get the color of a vertex from its `fillcolor` or `color` attribute.
+///
+/// Attributes set on the vertex itself take precedence over
+/// `order_attributes`, which take precedence over the configuration of its
+/// kind.
+fn get_vertex_color(
+    kind: SWCCompartmentKind,
+    vertex_attributes: &Attributes,
+    order_attributes: Option<&Attributes>,
+    config: &Config,
+) -> Option<[u8; 3]> {
+    let mut attributes = config.get_config(kind).to_attributes();
+    if let Some(order_attributes) = order_attributes {
+        attributes.extend(order_attributes);
+    }
+    attributes.extend(vertex_attributes);
+    return attributes
+        .get("fillcolor")
+        .or_else(|| attributes.get("color"))
+        .and_then(|color| parse_color(color));
+}
+
+fn attribute_to_gexf(name: &str, attribute_type: &str) -> String {
+    format!(
+        "      <attribute id=\"{}\" title=\"{}\" type=\"{}\"/>\n",
+        escape_xml(name),
+        escape_xml(name),
+        attribute_type
+    )
+}
+
+fn attvalue_to_gexf(name: &str, value: &str) -> String {
+    format!(
+        "          <attvalue for=\"{}\" value=\"{}\"/>\n",
+        escape_xml(name),
+        escape_xml(value)
+    )
+}
+
+#[cfg(test)]
+mod gexf_tests {
+    use super::*;
+    use crate::test_utils::{get_test_config, get_test_graph};
+
+    #[allow(clippy::expect_fun_call)]
+    fn get_node(gexf: &str, id: usize) -> String {
+        let start = gexf
+            .find(&format!("      <node id=\"{}\"", id))
+            .expect(&format!("Node {} is missing", id));
+        let end = start + gexf[start..].find("</node>\n").unwrap() + "</node>\n".len();
+        return gexf[start..end].to_string();
+    }
+
+    #[test]
+    fn nodes_are_laid_out_sized_and_colored() {
+        let gexf = get_test_graph().to_gexf(&get_test_config());
+        // The default configuration fills somatic vertices green, dendritic
+        // vertices blue and gives axonal vertices no color.
+        let expected = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <gexf xmlns=\"http://www.gexf.net/1.2draft\" \
+            xmlns:viz=\"http://www.gexf.net/1.2draft/viz\" version=\"1.2\">\n\
+            \x20 <meta>\n    <creator>swc2dot</creator>\n  </meta>\n\
+            \x20 <graph mode=\"static\" defaultedgetype=\"undirected\">\n\
+            \x20   <attributes class=\"node\">\n\
+            \x20     <attribute id=\"kind\" title=\"kind\" type=\"string\"/>\n\
+            \x20   </attributes>\n\
+            \x20   <nodes>\n\
+            \x20     <node id=\"1\" label=\"1\">\n\
+            \x20       <attvalues>\n\
+            \x20         <attvalue for=\"kind\" value=\"soma\"/>\n\
+            \x20       </attvalues>\n\
+            \x20       <viz:size value=\"2\"/>\n\
+            \x20       <viz:position x=\"0\" y=\"0\" z=\"0\"/>\n\
+            \x20       <viz:color r=\"0\" g=\"255\" b=\"0\"/>\n\
+            \x20     </node>\n\
+            \x20     <node id=\"2\" label=\"2\">\n\
+            \x20       <attvalues>\n\
+            \x20         <attvalue for=\"kind\" value=\"dendrite\"/>\n\
+            \x20       </attvalues>\n\
+            \x20       <viz:size value=\"1\"/>\n\
+            \x20       <viz:position x=\"0\" y=\"3\" z=\"0\"/>\n\
+            \x20       <viz:color r=\"0\" g=\"0\" b=\"255\"/>\n\
+            \x20     </node>\n\
+            \x20     <node id=\"3\" label=\"3\">\n\
+            \x20       <attvalues>\n\
+            \x20         <attvalue for=\"kind\" value=\"dendrite\"/>\n\
+            \x20       </attvalues>\n\
+            \x20       <viz:size value=\"0.5\"/>\n\
+            \x20       <viz:position x=\"4\" y=\"3\" z=\"0\"/>\n\
+            \x20       <viz:color r=\"0\" g=\"0\" b=\"255\"/>\n\
+            \x20     </node>\n\
+            \x20     <node id=\"4\" label=\"4\">\n\
+            \x20       <attvalues>\n\
+            \x20         <attvalue for=\"kind\" value=\"axon\"/>\n\
+            \x20       </attvalues>\n\
+            \x20       <viz:size value=\"1\"/>\n\
+            \x20       <viz:position x=\"0\" y=\"-4\" z=\"0\"/>\n\
+            \x20     </node>\n\
+            \x20   </nodes>\n\
+            \x20   <edges>\n\
+            \x20     <edge id=\"0\" source=\"1\" target=\"2\"/>\n\
+            \x20     <edge id=\"1\" source=\"1\" target=\"4\"/>\n\
+            \x20     <edge id=\"2\" source=\"2\" target=\"3\"/>\n\
+            \x20   </edges>\n\
+            \x20 </graph>\n\
+            </gexf>\n";
+        assert_eq!(gexf, expected);
+    }
+
+    #[test]
+    fn positions_follow_projection() {
+        let mut config = get_test_config();
+        config.set_projection(Some(Projection::xz().with_scale(2.0)));
+        let gexf = get_test_graph().to_gexf(&config);
+        assert_eq!(
+            get_node(&gexf, 3),
+            "      <node id=\"3\" label=\"3\">\n\
+             \x20       <attvalues>\n\
+             \x20         <attvalue for=\"kind\" value=\"dendrite\"/>\n\
+             \x20       </attvalues>\n\
+             \x20       <viz:size value=\"1\"/>\n\
+             \x20       <viz:position x=\"8\" y=\"0\" z=\"0\"/>\n\
+             \x20       <viz:color r=\"0\" g=\"0\" b=\"255\"/>\n\
+             \x20     </node>\n"
+        );
+    }
+
+    #[test]
+    fn vertex_colors_take_precedence_over_config() {
+        let mut graph = get_test_graph();
+        graph.set_vertex_attribute(4, "fillcolor", "#22a884".to_string());
+        let gexf = graph.to_gexf(&get_test_config());
+        assert_eq!(
+            get_node(&gexf, 4),
+            "      <node id=\"4\" label=\"4\">\n\
+             \x20       <attvalues>\n\
+             \x20         <attvalue for=\"kind\" value=\"axon\"/>\n\
+             \x20         <attvalue for=\"fillcolor\" value=\"#22a884\"/>\n\
+             \x20       </attvalues>\n\
+             \x20       <viz:size value=\"1\"/>\n\
+             \x20       <viz:position x=\"0\" y=\"-4\" z=\"0\"/>\n\
+             \x20       <viz:color r=\"34\" g=\"168\" b=\"132\"/>\n\
+             \x20     </node>\n"
+        );
+    }
+
+    #[test]
+    fn vertex_kind_attribute_is_left_out() {
+        let mut graph = get_test_graph();
+        graph.set_vertex_attribute(4, "kind", "spine".to_string());
+        let gexf = graph.to_gexf(&get_test_config());
+        assert_eq!(gexf, get_test_graph().to_gexf(&get_test_config()));
+    }
+
+    #[test]
+    fn edge_attributes_are_declared() {
+        let mut graph = get_test_graph();
+        graph.set_edge_number(1, 2, "length", 3.2);
+        let mut config = get_test_config();
+        config.set_edge_direction(EdgeDirection::ParentToChild);
+        let gexf = graph.to_gexf(&config);
+        let start = gexf.find("  <graph").unwrap();
+        let nodes = gexf.find("    <nodes>").unwrap();
+        let edges = gexf.find("    <edges>").unwrap();
+        assert_eq!(
+            &gexf[start..nodes],
+            "  <graph mode=\"static\" defaultedgetype=\"directed\">\n\
+             \x20   <attributes class=\"node\">\n\
+             \x20     <attribute id=\"kind\" title=\"kind\" type=\"string\"/>\n\
+             \x20   </attributes>\n\
+             \x20   <attributes class=\"edge\">\n\
+             \x20     <attribute id=\"length\" title=\"length\" type=\"double\"/>\n\
+             \x20   </attributes>\n"
+        );
+        assert_eq!(
+            &gexf[edges..],
+            "    <edges>\n\
+             \x20     <edge id=\"0\" source=\"1\" target=\"2\">\n\
+             \x20       <attvalues>\n\
+             \x20         <attvalue for=\"length\" value=\"3.2\"/>\n\
+             \x20       </attvalues>\n\
+             \x20     </edge>\n\
+             \x20     <edge id=\"1\" source=\"1\" target=\"4\"/>\n\
+             \x20     <edge id=\"2\" source=\"2\" target=\"3\"/>\n\
+             \x20   </edges>\n\
+             \x20 </graph>\n\
+             </gexf>\n"
+        );
+    }
+}
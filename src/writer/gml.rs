@@ -0,0 +1,187 @@
+#![allow(clippy::needless_return, clippy::redundant_static_lifetimes)]
+
+use crate::components::{Attributes, Graph};
+use crate::writer::network::Network;
+use crate::writer::EdgeDirection;
+
+/// Keys written for every node, which vertex attributes can't replace.
+static NODE_KEYS: &'static [&'static str] = &["id", "label", "kind", "x", "y", "z", "radius"];
+/// Keys written for every edge, which edge attributes can't replace.
+static EDGE_KEYS: &'static [&'static str] = &["source", "target"];
+
+/// Get a `String` representation of an object in GML format.
+pub trait ToGML {
+    fn to_gml(&self, direction: EdgeDirection) -> String;
+}
+
+impl ToGML for Graph {
+    /// Get a GML graph with a node per vertex and an edge per parent-child
+    /// pair.
+    ///
+    /// Nodes are identified by vertex id and labelled by their `label`
+    /// attribute, or by id if they don't have one, and have the kind,
+    /// position and radius of their compartment. Other attributes set on
+    /// vertices and edges are written as well, as numbers if they were set as
+    /// numbers and as strings otherwise. Attributes with keys that aren't
+    /// valid GML keys or that clash with the keys above are left out.
+    fn to_gml(&self, direction: EdgeDirection) -> String {
+        let network = Network::new(self, direction);
+        let mut gml = String::with_capacity(128 * self.len());
+        gml.push_str("Creator \"swc2dot\"\ngraph [\n");
+        gml.push_str(&format!(
+            "  directed {}\n",
+            if network.directed { 1 } else { 0 }
+        ));
+        for vertex in network.vertices.iter() {
+            let position = vertex.get_position();
+            gml.push_str("  node [\n");
+            gml.push_str(&format!("    id {}\n", vertex.get_id()));
+            let label = match vertex.get_attributes().get("label") {
+                Some(label) => quote_gml(label),
+                None => format!("\"{}\"", vertex.get_id()),
+            };
+            gml.push_str(&format!("    label {}\n", label));
+            gml.push_str(&format!(
+                "    kind {}\n",
+                quote_gml(vertex.get_kind().get_name())
+            ));
+            gml.push_str(&format!("    x {}\n", real_to_gml(position.x)));
+            gml.push_str(&format!("    y {}\n", real_to_gml(position.y)));
+            gml.push_str(&format!("    z {}\n", real_to_gml(position.z)));
+            gml.push_str(&format!(
+                "    radius {}\n",
+                real_to_gml(vertex.get_radius())
+            ));
+            gml.push_str(&attributes_to_gml(vertex.get_attributes(), NODE_KEYS));
+            gml.push_str("  ]\n");
+        }
+        for edge in network.edges.iter() {
+            gml.push_str("  edge [\n");
+            gml.push_str(&format!(
+                "    source {}\n",
+                network.vertices[edge.source].get_id()
+            ));
+            gml.push_str(&format!(
+                "    target {}\n",
+                network.vertices[edge.target].get_id()
+            ));
+            gml.push_str(&attributes_to_gml(&edge.attributes, EDGE_KEYS));
+            gml.push_str("  ]\n");
+        }
+        gml.push_str("]\n");
+        return gml;
+    }
+}
+
+/// Get a key-value line for each attribute with a valid key that isn't
+/// `reserved`.
+fn attributes_to_gml(attributes: &Attributes, reserved: &[&str]) -> String {
+    let mut gml = String::new();
+    for (key, value) in attributes.iter() {
+        let is_valid_key = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if is_valid_key && !reserved.contains(&key.as_str()) {
+            let value = if attributes.is_number(key) {
+                number_to_gml(value)
+            } else {
+                quote_gml(value)
+            };
+            gml.push_str(&format!("    {} {}\n", key, value));
+        }
+    }
+    return gml;
+}
+
+/// Get a number as a GML integer if it is whole and a GML real otherwise.
+fn number_to_gml(value: &str) -> String {
+    if let Ok(integer) = value.parse::<i64>() {
+        return integer.to_string();
+    }
+    match value.parse::<f64>() {
+        Ok(real) => real_to_gml(real),
+        Err(_) => quote_gml(value),
+    }
+}
+
+/// Get a number as a GML real, which always has a decimal point.
+fn real_to_gml(value: f64) -> String {
+    let real = value.to_string();
+    if real.contains('.') {
+        return real;
+    }
+    return format!("{}.0", real);
+}
+
+/// Quote a GML string, which can't contain double quotes.
+fn quote_gml(string: &str) -> String {
+    format!(
+        "\"{}\"",
+        string.replace('&', "&amp;").replace('"', "&quot;")
+    )
+}
+
+#[cfg(test)]
+mod gml_tests {
+    use super::*;
+    use crate::test_utils::get_test_graph;
+
+    static NODES: &'static str = "  node [\n    id 1\n    label \"1\"\n    kind \"soma\"\n    \
+        x 0.0\n    y 0.0\n    z 0.0\n    radius 2.0\n  ]\n\
+        \x20 node [\n    id 2\n    label \"2\"\n    kind \"dendrite\"\n    \
+        x 0.0\n    y 3.0\n    z 0.0\n    radius 1.0\n  ]\n\
+        \x20 node [\n    id 3\n    label \"3\"\n    kind \"dendrite\"\n    \
+        x 4.0\n    y 3.0\n    z 0.0\n    radius 0.5\n  ]\n\
+        \x20 node [\n    id 4\n    label \"4\"\n    kind \"axon\"\n    \
+        x 0.0\n    y -4.0\n    z 0.0\n    radius 1.0\n  ]\n";
+
+    #[test]
+    fn nodes_and_edges() {
+        let expected = format!(
+            "Creator \"swc2dot\"\ngraph [\n  directed 0\n{}\
+             \x20 edge [\n    source 1\n    target 2\n  ]\n\
+             \x20 edge [\n    source 1\n    target 4\n  ]\n\
+             \x20 edge [\n    source 2\n    target 3\n  ]\n\
+             ]\n",
+            NODES
+        );
+        assert_eq!(get_test_graph().to_gml(EdgeDirection::Undirected), expected);
+    }
+
+    #[test]
+    fn attributes_are_typed() {
+        let mut graph = get_test_graph();
+        graph.set_vertex_number(3, "branch_order", 1.0);
+        graph.set_vertex_attribute(3, "label", "tip".to_string());
+        graph.set_vertex_attribute(3, "note", "a \"b\"".to_string());
+        graph.set_vertex_attribute(3, "name", "007".to_string());
+        graph.set_edge_number(2, 3, "length", 4.5);
+        let expected = format!(
+            "Creator \"swc2dot\"\ngraph [\n  directed 1\n{}\
+             \x20 edge [\n    source 2\n    target 1\n  ]\n\
+             \x20 edge [\n    source 4\n    target 1\n  ]\n\
+             \x20 edge [\n    source 3\n    target 2\n    length 4.5\n  ]\n\
+             ]\n",
+            NODES
+                .replace("label \"3\"", "label \"tip\"")
+                .replace(
+                    "radius 0.5\n",
+                    "radius 0.5\n    branch_order 1\n    note \"a &quot;b&quot;\"\n    \
+                     name \"007\"\n"
+                )
+        );
+        assert_eq!(graph.to_gml(EdgeDirection::ChildToParent), expected);
+    }
+
+    #[test]
+    fn invalid_and_reserved_keys_are_left_out() {
+        let mut graph = get_test_graph();
+        graph.set_vertex_attribute(3, "fill-color", "red".to_string());
+        graph.set_vertex_attribute(3, "kind", "spine".to_string());
+        graph.set_vertex_attribute(3, "2nd", "a".to_string());
+        graph.set_edge_attribute(2, 3, "source", "5".to_string());
+        assert_eq!(
+            graph.to_gml(EdgeDirection::Undirected),
+            get_test_graph().to_gml(EdgeDirection::Undirected)
+        );
+    }
+}
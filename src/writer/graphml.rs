@@ -0,0 +1,313 @@
+#![allow(clippy::needless_return, clippy::redundant_static_lifetimes)]
+
+use crate::components::{Attributes, Graph};
+use crate::writer::xml::escape_xml;
+use crate::writer::{get_edge_attributes_with_chain, EdgeDirection};
+
+/// Attributes written for every vertex, with their GraphML types.
+static VERTEX_KEYS: &'static [(&'static str, &'static str)] = &[
+    ("kind", "string"),
+    ("x", "double"),
+    ("y", "double"),
+    ("z", "double"),
+    ("radius", "double"),
+    ("parent", "long"),
+];
+
+/// Get a `String` representation of an object in GraphML format.
+pub trait ToGraphML {
+    fn to_graphml(&self, direction: EdgeDirection) -> String;
+}
+
+impl ToGraphML for Graph {
+    /// Get a GraphML document with a node per vertex and an edge per
+    /// parent-child pair.
+    ///
+    /// Every node has the kind, position, radius and parent (except for
+    /// roots) of its compartment. Attributes set on vertices and edges are
+    /// written as well, except for vertex attributes named after one of these
+    /// compartment keys, which are left out.
+    fn to_graphml(&self, direction: EdgeDirection) -> String {
+        let vertex_keys: Vec<(String, &'static str)> = VERTEX_KEYS
+            .iter()
+            .map(|(name, attribute_type)| (name.to_string(), *attribute_type))
+            .chain(
+                get_attribute_keys(
+                    self.iter_vertices()
+                        .map(|(_, vertex)| vertex.get_attributes()),
+                )
+                .into_iter()
+                .filter(|(name, _)| !VERTEX_KEYS.iter().any(|(builtin, _)| builtin == name)),
+            )
+            .collect();
+        let edge_attributes: Vec<(usize, usize, Attributes)> = self
+            .iter_edges()
+            .map(|(parent_id, child_id)| {
+                let attributes = get_edge_attributes_with_chain(self, parent_id, child_id);
+                (parent_id, child_id, attributes)
+            })
+            .collect();
+        let edge_keys = get_attribute_keys(
+            edge_attributes
+                .iter()
+                .map(|(_, _, attributes)| attributes),
+        );
+
+        let mut graphml = String::with_capacity(128 * self.len());
+        graphml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        graphml.push_str(
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" \
+             xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+             xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns \
+             http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n",
+        );
+        for (index, (name, attribute_type)) in vertex_keys.iter().enumerate() {
+            graphml.push_str(&key_to_graphml("node", index, name, attribute_type));
+        }
+        for (index, (name, attribute_type)) in edge_keys.iter().enumerate() {
+            graphml.push_str(&key_to_graphml("edge", index, name, attribute_type));
+        }
+
+        let edge_default = match direction {
+            EdgeDirection::Undirected => "undirected",
+            _ => "directed",
+        };
+        graphml.push_str(&format!(
+            "  <graph id=\"G\" edgedefault=\"{}\">\n",
+            edge_default
+        ));
+        for (id, vertex) in self.iter_vertices() {
+            graphml.push_str(&format!("    <node id=\"{}\">\n", id));
+            let position = vertex.get_position();
+            let mut data = vec![
+                ("kind", vertex.get_kind().get_name().to_string()),
+                ("x", position.x.to_string()),
+                ("y", position.y.to_string()),
+                ("z", position.z.to_string()),
+                ("radius", vertex.get_radius().to_string()),
+            ];
+            if let Some(parent_id) = vertex.get_parent_id() {
+                data.push(("parent", parent_id.to_string()));
+            }
+            for (key, value) in data {
+                graphml.push_str(&data_to_graphml("node", &vertex_keys, key, &value));
+            }
+            for (key, value) in vertex.get_attributes().iter() {
+                if !VERTEX_KEYS.iter().any(|(builtin, _)| builtin == key) {
+                    graphml.push_str(&data_to_graphml("node", &vertex_keys, key, value));
+                }
+            }
+            graphml.push_str("    </node>\n");
+        }
+        for (parent_id, child_id, attributes) in edge_attributes.iter() {
+            let (source_id, target_id) = match direction {
+                EdgeDirection::ChildToParent => (child_id, parent_id),
+                _ => (parent_id, child_id),
+            };
+            if attributes.is_empty() {
+                graphml.push_str(&format!(
+                    "    <edge source=\"{}\" target=\"{}\"/>\n",
+                    source_id, target_id
+                ));
+            } else {
+                graphml.push_str(&format!(
+                    "    <edge source=\"{}\" target=\"{}\">\n",
+                    source_id, target_id
+                ));
+                for (key, value) in attributes.iter() {
+                    graphml.push_str(&data_to_graphml("edge", &edge_keys, key, value));
+                }
+                graphml.push_str("    </edge>\n");
+            }
+        }
+        graphml.push_str("  </graph>\n</graphml>\n");
+        return graphml;
+    }
+}
+
+/// Get the names of attributes in order of first appearance, with their
+/// GraphML types (which GEXF shares).
+///
+/// Attributes are doubles if all of their values were set as numbers (see
+/// `Attributes::is_number()`), and strings otherwise.
+pub fn get_attribute_keys<'a>(
+    attributes: impl Iterator<Item = &'a Attributes>,
+) -> Vec<(String, &'static str)> {
+    let mut keys: Vec<(String, &'static str)> = Vec::new();
+    for attributes in attributes {
+        for (name, _) in attributes.iter() {
+            let value_type = if attributes.is_number(name) {
+                "double"
+            } else {
+                "string"
+            };
+            match keys.iter_mut().find(|(key, _)| key == name) {
+                Some((_, key_type)) if *key_type != value_type => *key_type = "string",
+                Some(_) => {}
+                None => keys.push((name.clone(), value_type)),
+            }
+        }
+    }
+    return keys;
+}
+
+fn key_to_graphml(domain: &str, index: usize, name: &str, attribute_type: &str) -> String {
+    format!(
+        "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>\n",
+        get_key_id(domain, index),
+        domain,
+        escape_xml(name),
+        attribute_type
+    )
+}
+
+/// Get a data element for the value of the key called `name` in `keys`.
+#[allow(clippy::expect_fun_call)]
+fn data_to_graphml(
+    domain: &str,
+    keys: &[(String, &'static str)],
+    name: &str,
+    value: &str,
+) -> String {
+    let index = keys
+        .iter()
+        .position(|(key, _)| key == name)
+        .expect(&format!("Key {} was not declared", name));
+    format!(
+        "      <data key=\"{}\">{}</data>\n",
+        get_key_id(domain, index),
+        escape_xml(value)
+    )
+}
+
+/// Get the id of a key, which must be unique across nodes and edges.
+///
+/// Keys are numbered rather than named after their attribute, since ids must
+/// be XML name tokens and attribute names can contain any character.
+fn get_key_id(domain: &str, index: usize) -> String {
+    let prefix = if domain == "node" { "v" } else { "e" };
+    return format!("{}_{}", prefix, index);
+}
+
+#[cfg(test)]
+mod graphml_tests {
+    use super::*;
+    use crate::test_utils::get_test_graph;
+
+    static HEADER: &'static str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" \
+        xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+        xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns \
+        http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n\
+        \x20 <key id=\"v_0\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n\
+        \x20 <key id=\"v_1\" for=\"node\" attr.name=\"x\" attr.type=\"double\"/>\n\
+        \x20 <key id=\"v_2\" for=\"node\" attr.name=\"y\" attr.type=\"double\"/>\n\
+        \x20 <key id=\"v_3\" for=\"node\" attr.name=\"z\" attr.type=\"double\"/>\n\
+        \x20 <key id=\"v_4\" for=\"node\" attr.name=\"radius\" attr.type=\"double\"/>\n\
+        \x20 <key id=\"v_5\" for=\"node\" attr.name=\"parent\" attr.type=\"long\"/>\n";
+
+    /// Get the node elements of the test graph, followed by `extra` data
+    /// for each vertex.
+    fn get_nodes(extra: &[&str]) -> String {
+        let compartments = [
+            ("1", "soma", "0", "0", "2", None),
+            ("2", "dendrite", "0", "3", "1", Some("1")),
+            ("3", "dendrite", "4", "3", "0.5", Some("2")),
+            ("4", "axon", "0", "-4", "1", Some("1")),
+        ];
+        let mut nodes = String::new();
+        for (i, (id, kind, x, y, radius, parent)) in compartments.iter().enumerate() {
+            nodes.push_str(&format!(
+                "    <node id=\"{}\">\n\
+                 \x20     <data key=\"v_0\">{}</data>\n\
+                 \x20     <data key=\"v_1\">{}</data>\n\
+                 \x20     <data key=\"v_2\">{}</data>\n\
+                 \x20     <data key=\"v_3\">0</data>\n\
+                 \x20     <data key=\"v_4\">{}</data>\n",
+                id, kind, x, y, radius
+            ));
+            if let Some(parent) = parent {
+                nodes.push_str(&format!("      <data key=\"v_5\">{}</data>\n", parent));
+            }
+            nodes.push_str(extra.get(i).unwrap_or(&""));
+            nodes.push_str("    </node>\n");
+        }
+        return nodes;
+    }
+
+    #[test]
+    fn undirected() {
+        let expected = format!(
+            "{}  <graph id=\"G\" edgedefault=\"undirected\">\n{}\
+             \x20   <edge source=\"1\" target=\"2\"/>\n\
+             \x20   <edge source=\"1\" target=\"4\"/>\n\
+             \x20   <edge source=\"2\" target=\"3\"/>\n\
+             \x20 </graph>\n</graphml>\n",
+            HEADER,
+            get_nodes(&[])
+        );
+        assert_eq!(get_test_graph().to_graphml(EdgeDirection::Undirected), expected);
+    }
+
+    #[test]
+    fn directed_child_to_parent() {
+        let expected = format!(
+            "{}  <graph id=\"G\" edgedefault=\"directed\">\n{}\
+             \x20   <edge source=\"2\" target=\"1\"/>\n\
+             \x20   <edge source=\"4\" target=\"1\"/>\n\
+             \x20   <edge source=\"3\" target=\"2\"/>\n\
+             \x20 </graph>\n</graphml>\n",
+            HEADER,
+            get_nodes(&[])
+        );
+        assert_eq!(get_test_graph().to_graphml(EdgeDirection::ChildToParent), expected);
+    }
+
+    #[test]
+    fn attributes_are_typed_and_escaped() {
+        let mut graph = get_test_graph();
+        graph.set_vertex_number(1, "branch_order", 0.0);
+        graph.set_vertex_number(2, "branch_order", 1.0);
+        graph.set_vertex_attribute(2, "fill color", "a & b".to_string());
+        graph.set_vertex_attribute(3, "branch_order", "1".to_string());
+        graph.set_edge_number(1, 2, "mean radius (µm)", 1.5);
+        let expected = format!(
+            "{}\
+             \x20 <key id=\"v_6\" for=\"node\" attr.name=\"branch_order\" attr.type=\"string\"/>\n\
+             \x20 <key id=\"v_7\" for=\"node\" attr.name=\"fill color\" attr.type=\"string\"/>\n\
+             \x20 <key id=\"e_0\" for=\"edge\" attr.name=\"mean radius (µm)\" \
+             attr.type=\"double\"/>\n\
+             \x20 <graph id=\"G\" edgedefault=\"undirected\">\n{}\
+             \x20   <edge source=\"1\" target=\"2\">\n\
+             \x20     <data key=\"e_0\">1.5</data>\n\
+             \x20   </edge>\n\
+             \x20   <edge source=\"1\" target=\"4\"/>\n\
+             \x20   <edge source=\"2\" target=\"3\"/>\n\
+             \x20 </graph>\n</graphml>\n",
+            HEADER,
+            get_nodes(&[
+                "      <data key=\"v_6\">0</data>\n",
+                "      <data key=\"v_6\">1</data>\n      <data key=\"v_7\">a &amp; b</data>\n",
+                "      <data key=\"v_6\">1</data>\n",
+            ])
+        );
+        assert_eq!(graph.to_graphml(EdgeDirection::Undirected), expected);
+    }
+
+    #[test]
+    fn attributes_named_after_compartment_keys_are_left_out() {
+        let mut graph = get_test_graph();
+        graph.set_vertex_attribute(2, "kind", "spine".to_string());
+        graph.set_vertex_attribute(2, "radius", "3".to_string());
+        let expected = format!(
+            "{}  <graph id=\"G\" edgedefault=\"undirected\">\n{}\
+             \x20   <edge source=\"1\" target=\"2\"/>\n\
+             \x20   <edge source=\"1\" target=\"4\"/>\n\
+             \x20   <edge source=\"2\" target=\"3\"/>\n\
+             \x20 </graph>\n</graphml>\n",
+            HEADER,
+            get_nodes(&[])
+        );
+        assert_eq!(graph.to_graphml(EdgeDirection::Undirected), expected);
+    }
+}
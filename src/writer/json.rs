@@ -0,0 +1,41 @@
+#![allow(clippy::needless_return)]
+
+/// Quote a string for use in JSON.
+pub fn quote_json(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    return quoted;
+}
+
+/// Join JSON objects describing one file each.
+///
+/// A single object is written as is, and several are written as an array.
+pub fn json_records_to_string(objects: &[String]) -> String {
+    if objects.len() == 1 {
+        return format!("{}\n", objects[0]);
+    }
+    return format!("[\n{}\n]\n", objects.join(",\n"));
+}
+
+#[cfg(test)]
+mod json_tests {
+    use super::*;
+
+    #[test]
+    fn quote_json_escapes_special_characters() {
+        assert_eq!(quote_json("a \"b\"\\c"), "\"a \\\"b\\\"\\\\c\"");
+        assert_eq!(quote_json("tab\there\u{1}"), "\"tab\\there\\u0001\"");
+    }
+}
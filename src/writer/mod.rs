@@ -1,15 +1,49 @@
+#![allow(
+    clippy::needless_return,
+    clippy::redundant_field_names,
+    clippy::redundant_static_lifetimes
+)]
+
 use std::cmp::max;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use itertools::Itertools;
 
-use crate::components::{Graph, ShortTree, Vertex};
+use crate::components::{Attributes, Graph, ShortTree, Vertex};
 use crate::config::Config;
-use crate::swc_parser::SWCCompartmentKind;
-
+use crate::swc_parser::{Metadata, SWCCompartmentKind};
+
+mod colormap;
+mod csv;
+mod gexf;
+mod gml;
+mod graphml;
+mod json;
+mod network;
+mod node_link;
+mod pajek;
+mod projection;
+mod radius_scale;
+mod sholl;
+mod stats;
 mod string_buffer;
-
-pub use string_buffer::{StringBuffer, Indent, get_indent};
+mod swc;
+mod table;
+mod xml;
+
+pub use colormap::{ColorScale, Colormap};
+pub use gexf::ConfiguredToGEXF;
+pub use gml::ToGML;
+pub use graphml::ToGraphML;
+pub use node_link::ToJSON;
+pub use pajek::ToPajek;
+pub use projection::Projection;
+pub use radius_scale::{RadiusMapping, RadiusScale};
+pub use sholl::{sholl_to_string, ShollFormat};
+pub use stats::{stats_to_string, StatsFormat};
+pub use string_buffer::{get_indent, Indent, StringBuffer};
+pub use swc::{SWCFormat, ToSWC};
+pub use table::ToTable;
 
 /// Get a `String` representation of an object in DOT format.
 pub trait ToDot {
@@ -52,7 +86,7 @@ mod vertex_todot_tests {
     fn no_leading_newline_zero_indent() {
         let vertex = get_test_vertex();
         assert!(
-            vertex.to_dot(false, Indent::zero()).chars().next().unwrap() != '\n',
+            !vertex.to_dot(false, Indent::zero()).starts_with('\n'),
             "Expected first char to not be newline when arguemnt `newline=false`"
         )
     }
@@ -76,6 +110,115 @@ mod vertex_todot_tests {
 
 static GRAPH_STRING_MAX_BUFSIZE: usize = 5242880;
 
+/// Whether edges are undirected, and if not, which way they point.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EdgeDirection {
+    Undirected,
+    /// Edges point away from the root, eg from the soma towards the tips.
+    ParentToChild,
+    /// Edges point towards the root.
+    ChildToParent,
+}
+
+impl EdgeDirection {
+    /// Get the keyword that starts a DOT graph with edges of this kind.
+    pub fn get_graph_keyword(&self) -> &'static str {
+        match self {
+            EdgeDirection::Undirected => "graph",
+            _ => "digraph",
+        }
+    }
+
+    /// Get the DOT edge operator for edges of this kind.
+    pub fn get_edge_op(&self) -> &'static str {
+        match self {
+            EdgeDirection::Undirected => "--",
+            _ => "->",
+        }
+    }
+}
+
+/// Format for graphs converted from SWC files.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    GraphML,
+    Gexf,
+    /// Node-link JSON, as read by NetworkX and D3.
+    Json,
+    Gml,
+    /// Pajek NET.
+    Pajek,
+    /// Delimited table with a row per edge.
+    EdgeList,
+    /// Delimited table with a row per vertex.
+    Nodes,
+    Swc,
+}
+
+impl GraphFormat {
+    /// Get the file extension for graphs in this format, including the dot.
+    ///
+    /// Tables get a suffix before their extension so that the edge list and
+    /// node table of a file can be written next to each other, and so does
+    /// SWC so that converting a file doesn't replace it.
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => ".dot",
+            GraphFormat::GraphML => ".graphml",
+            GraphFormat::Gexf => ".gexf",
+            GraphFormat::Json => ".json",
+            GraphFormat::Gml => ".gml",
+            GraphFormat::Pajek => ".net",
+            GraphFormat::EdgeList => ".edges.csv",
+            GraphFormat::Nodes => ".nodes.csv",
+            GraphFormat::Swc => ".converted.swc",
+        }
+    }
+
+    /// Get the format of a file from its extension (without the dot), if it
+    /// is a known graph format.
+    ///
+    /// Tables aren't inferred, since the same extension is used for both.
+    pub fn from_extension(extension: &str) -> Option<GraphFormat> {
+        match extension.to_lowercase().as_str() {
+            "dot" | "gv" => Some(GraphFormat::Dot),
+            "graphml" => Some(GraphFormat::GraphML),
+            "gexf" => Some(GraphFormat::Gexf),
+            "json" => Some(GraphFormat::Json),
+            "gml" => Some(GraphFormat::Gml),
+            "net" => Some(GraphFormat::Pajek),
+            "swc" => Some(GraphFormat::Swc),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod graph_format_tests {
+    use super::*;
+
+    #[test]
+    fn extensions_round_trip() {
+        let formats = [
+            GraphFormat::Dot,
+            GraphFormat::GraphML,
+            GraphFormat::Gexf,
+            GraphFormat::Json,
+            GraphFormat::Gml,
+            GraphFormat::Pajek,
+        ];
+        for format in formats.iter() {
+            let extension = &format.get_extension()[1..];
+            assert_eq!(GraphFormat::from_extension(extension), Some(*format));
+        }
+        assert_eq!(GraphFormat::from_extension("GV"), Some(GraphFormat::Dot));
+        assert_eq!(GraphFormat::from_extension("swc"), Some(GraphFormat::Swc));
+        assert_eq!(GraphFormat::from_extension("txt"), None);
+        assert_eq!(GraphFormat::from_extension("csv"), None);
+    }
+}
+
 /// Get a configured `String` representation of an object in DOT format.
 ///
 /// # See also
@@ -90,7 +233,19 @@ impl ConfiguredToDot for Graph {
         let mut graph_string =
             String::with_capacity(max(64 * self.len(), GRAPH_STRING_MAX_BUFSIZE));
 
-        graph_string.push_str("graph{");
+        graph_string.push_str(config.get_edge_direction().get_graph_keyword());
+        graph_string.push('{');
+
+        // SWC header and metadata
+        graph_string.push_str(&header_comment_to_dot(
+            self.get_comments(),
+            Indent::flat(indent.main + 1),
+        ));
+        graph_string.push_str(
+            &self
+                .get_metadata()
+                .to_dot(true, Indent::flat(indent.main + 1)),
+        );
 
         // Node configuration
         use vertex_config_formatter::VertexConfigFormatter;
@@ -100,15 +255,24 @@ impl ConfiguredToDot for Graph {
             buffers.weak_push_config_str(kind, &config.get_config(kind).to_dot(false, Indent::zero()));
             buffers.weak_push_config_str(kind, " ");
         }
-        for (_, vertex) in self.iter_vertices() {
-            buffers.push_config_str(vertex.get_kind(), &vertex.to_dot(false, Indent::zero()));
+        let order_attributes = config.get_order_attributes(self);
+        for (id, vertex) in self.iter_vertices() {
+            buffers.push_config_str(
+                vertex.get_kind(),
+                &vertex_to_dot_with_config(vertex, config, order_attributes.get(id)),
+            );
         }
 
         graph_string.push_str(&buffers.to_dot(false, Indent::flat(indent.main + 1)));
 
         // Write edges
         for short_tree in self.iter_short_trees() {
-            graph_string.push_str(&short_tree.to_dot(true, Indent::flat(indent.main + 1)));
+            graph_string.push_str(&short_tree_to_dot_with_config(
+                &short_tree,
+                self,
+                Indent::flat(indent.main + 1),
+                config,
+            ));
         }
         graph_string.push_str("\n}");
 
@@ -117,12 +281,372 @@ impl ConfiguredToDot for Graph {
     }
 }
 
+#[cfg(test)]
+mod graph_todot_tests {
+    use super::*;
+    use crate::test_utils::{get_test_config, graph_from_swc};
+
+    /// Get a graph with one compartment of each kind, listed out of kind order.
+    fn get_test_graph() -> Graph {
+        let swc = "1 1 0 0 0 1 -1\n\
+                   2 5 1 0 0 1 1\n\
+                   3 3 2 0 0 1 2\n\
+                   4 2 3 0 0 1 3\n\
+                   5 4 4 0 0 1 4\n\
+                   6 0 5 0 0 1 5\n";
+        return graph_from_swc(swc);
+    }
+
+    #[test]
+    fn output_is_identical_between_runs() {
+        let config = get_test_config();
+        let expected = get_test_graph().to_dot(false, Indent::zero(), &config);
+        for _ in 0..10 {
+            assert_eq!(
+                get_test_graph().to_dot(false, Indent::zero(), &config),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn vertices_are_pinned_if_projection_is_set() {
+        let mut config = get_test_config();
+        config.set_projection(Some(Projection::xy().with_scale(2.0)));
+        let dot = get_test_graph().to_dot(false, Indent::zero(), &config);
+        assert!(dot.contains("1 [pos=\"0,0!\"]; "));
+        assert!(dot.contains("6 [pos=\"10,0!\"]; "));
+    }
+
+    #[test]
+    fn vertices_have_no_position_by_default() {
+        let dot = get_test_graph().to_dot(false, Indent::zero(), &get_test_config());
+        assert!(!dot.contains("pos="));
+    }
+
+    #[test]
+    fn sizes_follow_radius_if_radius_scale_is_set() {
+        let mut config = get_test_config();
+        config.set_radius_scale(Some(RadiusScale::new(RadiusMapping::Linear)));
+        let dot = get_test_graph().to_dot(false, Indent::zero(), &config);
+        assert!(dot.contains("2 [width=0.0278,height=0.0278,fixedsize=true]; "));
+        assert!(dot.contains("1 -- 2 [penwidth=2];"));
+    }
+
+    #[test]
+    fn vertex_attributes_are_written() {
+        let mut graph = get_test_graph();
+        graph.set_vertex_attribute(3, "label", "branch point".to_string());
+        let dot = graph.to_dot(false, Indent::zero(), &get_test_config());
+        assert!(dot.contains("3 [label=\"branch point\"]; "));
+        assert!(!dot.contains("2 ["), "Vertex without attributes written with a list");
+    }
+
+    #[test]
+    fn vertex_attributes_override_config() {
+        let mut config = get_test_config();
+        config.set_projection(Some(Projection::xy()));
+        let mut graph = get_test_graph();
+        graph.set_vertex_attribute(2, "pos", "0,0!".to_string());
+        let dot = graph.to_dot(false, Indent::zero(), &config);
+        assert!(dot.contains("2 [pos=\"0,0!\"]; "));
+    }
+
+    #[test]
+    fn edge_attributes_are_written() {
+        let mut graph = get_test_graph();
+        graph.set_edge_attribute(2, 3, "color", "red".to_string());
+        let dot = graph.to_dot(false, Indent::zero(), &get_test_config());
+        assert!(dot.contains("\n    2 -- 3 [color=red];"));
+        assert!(!dot.contains("\n    2;"), "Vertex with children written as a leaf");
+        assert!(dot.contains("\n    1 -- 2;"));
+    }
+
+    #[test]
+    fn collapsed_edges_have_chain_measurements() {
+        let graph = graph_from_swc("1 1 0 0 0 1 -1\n2 3 3 0 0 1 1\n3 3 3 4 0 1 2\n");
+        let mut collapsed = graph.collapse();
+        collapsed.set_edge_attribute(1, 3, "length", "1".to_string());
+        let dot = collapsed.to_dot(false, Indent::zero(), &get_test_config());
+        assert!(dot.contains(
+            "\n    1 -- 3 [length=7,compartments=2,mean_radius=1,surface_area=43.9823,\
+             volume=21.9911];"
+        ));
+    }
+
+    #[test]
+    fn undirected_by_default() {
+        let dot = get_test_graph().to_dot(false, Indent::zero(), &get_test_config());
+        assert!(dot.starts_with("graph{"));
+        assert!(!dot.contains("->"));
+    }
+
+    #[test]
+    fn directed_parent_to_child() {
+        let mut config = get_test_config();
+        config.set_edge_direction(EdgeDirection::ParentToChild);
+        let mut graph = get_test_graph();
+        graph.set_edge_attribute(2, 3, "color", "red".to_string());
+        let dot = graph.to_dot(false, Indent::zero(), &config);
+        assert!(dot.starts_with("digraph{"));
+        assert!(dot.contains("\n    1 -> 2;"));
+        assert!(dot.contains("\n    2 -> 3 [color=red];"));
+        assert!(!dot.contains("--"));
+    }
+
+    #[test]
+    fn directed_child_to_parent() {
+        let mut config = get_test_config();
+        config.set_edge_direction(EdgeDirection::ChildToParent);
+        let mut graph = get_test_graph();
+        graph.set_edge_attribute(2, 3, "color", "red".to_string());
+        let dot = graph.to_dot(false, Indent::zero(), &config);
+        assert!(dot.starts_with("digraph{"));
+        assert!(dot.contains("\n    2 -> 1;"));
+        assert!(dot.contains("\n    3 -> 2 [color=red];"));
+    }
+
+    #[test]
+    #[allow(clippy::expect_fun_call)]
+    fn node_configuration_is_ordered_by_kind() {
+        let config = get_test_config();
+        let dot = get_test_graph().to_dot(false, Indent::zero(), &config);
+        let mut last_position = 0;
+        for kind in SWCCompartmentKind::iter() {
+            let position = dot
+                .find(&format!("/* Configuration for {} vertices. */", kind))
+                .expect(&format!("Missing configuration block for {:?}", kind));
+            assert!(
+                position > last_position,
+                "Configuration for {:?} vertices is out of order",
+                kind
+            );
+            last_position = position;
+        }
+    }
+}
+
+/// Get a DOT representation of a single vertex with its attributes.
+///
+/// Attributes set on the vertex itself take precedence over
+/// `order_attributes` (from the `branch_order` and `strahler_order` groups of
+/// `config`), which take precedence over those derived from the rest of
+/// `config`. A vertex without attributes is written as just its id.
+fn vertex_to_dot_with_config(
+    vertex: &Vertex,
+    config: &Config,
+    order_attributes: Option<&Attributes>,
+) -> String {
+    let mut attributes = Attributes::new();
+    if let Some(projection) = config.get_projection() {
+        attributes.insert("pos", projection.to_pos(&vertex.get_position()));
+    }
+    if let Some(radius_scale) = config.get_radius_scale() {
+        attributes.extend(&radius_scale.get_vertex_attributes(vertex.get_radius()));
+    }
+    if let Some(order_attributes) = order_attributes {
+        attributes.extend(order_attributes);
+    }
+    attributes.extend(vertex.get_attributes());
+
+    if attributes.is_empty() {
+        return vertex.to_dot(false, Indent::zero());
+    }
+    return format!("{} {}; ", vertex.get_id(), attributes.to_dot(false, Indent::zero()));
+}
+
+/// Get attributes of the edge between a vertex and one of its children.
+///
+/// Attributes set on the edge itself take precedence over those derived from
+/// `config`.
+#[allow(clippy::expect_fun_call)]
+fn get_edge_attributes_with_config(
+    graph: &Graph,
+    parent_id: usize,
+    child_id: usize,
+    config: &Config,
+) -> Attributes {
+    let mut attributes = Attributes::new();
+    if let Some(radius_scale) = config.get_radius_scale() {
+        let child = graph.get_vertex(child_id).expect(&format!(
+            "Child {} of vertex {} is not in the graph",
+            child_id, parent_id
+        ));
+        attributes.extend(&radius_scale.get_edge_attributes(child.get_radius()));
+    }
+    attributes.extend(&get_edge_attributes_with_chain(graph, parent_id, child_id));
+    return attributes;
+}
+
+/// Get attributes of the edge between a vertex and one of its children,
+/// followed by the measurements of the chain it stands for in collapsed
+/// graphs (see `Graph::collapse()`).
+///
+/// Chain measurements are `length`, `compartments`, `mean_radius`,
+/// `surface_area` and `volume`, rounded to 4 decimal places, and take
+/// precedence over attributes set on the edge.
+pub fn get_edge_attributes_with_chain(
+    graph: &Graph,
+    parent_id: usize,
+    child_id: usize,
+) -> Attributes {
+    let mut attributes = Attributes::new();
+    if let Some(edge_attributes) = graph.get_edge_attributes(parent_id, child_id) {
+        attributes.extend(edge_attributes);
+    }
+    if let Some(chain) = graph.get_chain(parent_id, child_id) {
+        attributes.insert_number("length", chain.segment.length);
+        attributes.insert_number("compartments", chain.compartments as f64);
+        attributes.insert_number("mean_radius", chain.segment.mean_radius);
+        attributes.insert_number("surface_area", chain.segment.surface_area);
+        attributes.insert_number("volume", chain.segment.volume);
+    }
+    return attributes;
+}
+
+/// Get DOT statements for the edges of a `ShortTree`.
+///
+/// Edges without attributes are kept together in the compact `a -- {b, c};`
+/// form, and each edge with attributes gets its own `a -- d [k=v];` statement.
+fn short_tree_to_dot_with_config(
+    short_tree: &ShortTree,
+    graph: &Graph,
+    indent: Indent,
+    config: &Config,
+) -> String {
+    let root_id = short_tree.get_root_id();
+    let mut plain_child_ids = Vec::with_capacity(short_tree.get_child_ids().len());
+    let mut edges_str = String::new();
+    for child_id in short_tree.get_child_ids() {
+        let attributes = get_edge_attributes_with_config(graph, root_id, *child_id, config);
+        if attributes.is_empty() {
+            plain_child_ids.push(*child_id);
+        } else {
+            let direction = config.get_edge_direction();
+            let (tail_id, head_id) = match direction {
+                EdgeDirection::ChildToParent => (*child_id, root_id),
+                _ => (root_id, *child_id),
+            };
+            let mut edge_buf = StringBuffer::new(true, indent, 64);
+            edge_buf.push_str(&format!(
+                "{} {} {} {};",
+                tail_id,
+                direction.get_edge_op(),
+                head_id,
+                attributes.to_dot(false, Indent::zero())
+            ));
+            edges_str.push_str(&edge_buf.to_string());
+        }
+    }
+
+    // Leaves are still declared on their own, as they always have been.
+    if !plain_child_ids.is_empty() || short_tree.get_child_ids().is_empty() {
+        let plain_tree = ShortTree::new(root_id, plain_child_ids);
+        edges_str.insert_str(
+            0,
+            &short_tree_to_dot(&plain_tree, config.get_edge_direction(), true, indent),
+        );
+    }
+    return edges_str;
+}
+
+impl ToDot for Attributes {
+    /// Get a DOT attribute list, eg `[color=red,label="a b"]`.
+    ///
+    /// Keys and values are quoted if needed.
+    fn to_dot(&self, leading_newline: bool, indent: Indent) -> String {
+        let mut attributes_buf = StringBuffer::new(leading_newline, indent, 64);
+        if !self.is_empty() {
+            attributes_buf.push_str(&format!(
+                "[{}]",
+                self.iter()
+                    .map(|(key, value)| format!("{}={}", to_dot_id(key), to_dot_id(value)))
+                    .join(",")
+            ));
+        }
+        return attributes_buf.to_string();
+    }
+}
+
+/// DOT keywords, which can't be used as IDs without quotes.
+static DOT_KEYWORDS: &'static [&'static str] =
+    &["node", "edge", "graph", "digraph", "subgraph", "strict"];
+
+/// Get a string as a DOT ID, quoting it unless it is a plain identifier or number.
+///
+/// Keywords are matched case-insensitively, like DOT does.
+fn to_dot_id(string: &str) -> String {
+    let is_identifier = string
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && string.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !DOT_KEYWORDS
+            .iter()
+            .any(|keyword| keyword.eq_ignore_ascii_case(string));
+    let is_number = string.parse::<f64>().is_ok()
+        && string
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == '-');
+    if is_identifier || is_number {
+        return string.to_string();
+    }
+    return quote(string);
+}
+
+#[cfg(test)]
+mod attributes_todot_tests {
+    use super::*;
+
+    #[test]
+    fn plain_ids_are_not_quoted() {
+        let mut attributes = Attributes::new();
+        attributes.insert("color", "red".to_string());
+        attributes.insert("penwidth", "-1.5".to_string());
+        assert_eq!(
+            attributes.to_dot(false, Indent::zero()),
+            "[color=red,penwidth=-1.5]"
+        );
+    }
+
+    #[test]
+    fn other_values_are_quoted() {
+        let mut attributes = Attributes::new();
+        attributes.insert("label", "a \"b\"".to_string());
+        attributes.insert("pos", "1,2!".to_string());
+        assert_eq!(
+            attributes.to_dot(false, Indent::zero()),
+            "[label=\"a \\\"b\\\"\",pos=\"1,2!\"]"
+        );
+    }
+
+    #[test]
+    fn keywords_are_quoted() {
+        let mut attributes = Attributes::new();
+        attributes.insert("label", "node".to_string());
+        attributes.insert("xlabel", "Graph".to_string());
+        attributes.insert("comment", "nodes".to_string());
+        assert_eq!(
+            attributes.to_dot(false, Indent::zero()),
+            "[label=\"node\",xlabel=\"Graph\",comment=nodes]"
+        );
+    }
+
+    #[test]
+    fn no_attributes_yields_empty_string() {
+        assert_eq!(Attributes::new().to_dot(false, Indent::zero()), "");
+    }
+}
+
 mod vertex_config_formatter {
     use super::*;
 
     /// Pretty formatting of `Vertex` attributes in DOT language.
+    ///
+    /// Configuration blocks are written in order of compartment kind so that
+    /// the output is the same every time.
     pub struct VertexConfigFormatter {
-        vertex_config_strings: HashMap<SWCCompartmentKind, StringBuffer>,
+        vertex_config_strings: BTreeMap<SWCCompartmentKind, StringBuffer>,
     }
 
     impl VertexConfigFormatter {
@@ -131,7 +655,7 @@ mod vertex_config_formatter {
             indent: Indent,
             capacity: usize,
         ) -> VertexConfigFormatter {
-            let mut vertex_config_strings = HashMap::with_capacity(6);
+            let mut vertex_config_strings = BTreeMap::new();
 
             for compartment_kind in SWCCompartmentKind::iter() {
                 // Allocate buffer for vertex configuration settings for this compartment type.
@@ -145,7 +669,7 @@ mod vertex_config_formatter {
                 ));
                 compartment_config_string.newline();
 
-                // Insert it into HashMap that will be stored in the VertexConfigFormatter.
+                // Insert it into BTreeMap that will be stored in the VertexConfigFormatter.
                 vertex_config_strings.insert(compartment_kind, compartment_config_string);
             }
 
@@ -155,6 +679,7 @@ mod vertex_config_formatter {
             }
         }
 
+        #[allow(clippy::expect_fun_call)]
         pub fn push_config_str(&mut self, vertex_kind: SWCCompartmentKind, string: &str) {
             let config_buffer: &mut StringBuffer = self
                 .vertex_config_strings
@@ -178,6 +703,7 @@ mod vertex_config_formatter {
         /// compartments of the given type (if any exist). If there are no compartments of the
         /// given type, `push_config_str()` will never be called, and the configuration details
         /// added using `weak_push_config_str()` will be left out of the output of `to_dot()`.
+        #[allow(clippy::expect_fun_call)]
         pub fn weak_push_config_str(&mut self, vertex_kind: SWCCompartmentKind, string: &str) {
             let config_buffer: &mut StringBuffer = self
                 .vertex_config_strings
@@ -238,6 +764,137 @@ mod vertex_config_formatter {
 
             assert_eq!(formatter.to_dot(true, Indent::flat(1)), "");
         }
+
+        #[test]
+        #[allow(clippy::expect_fun_call)]
+        fn blocks_are_ordered_by_kind() {
+            let mut formatter = VertexConfigFormatter::new(true, Indent::flat(1), 1024);
+
+            // Push in the reverse of the expected order.
+            let kinds: Vec<SWCCompartmentKind> = SWCCompartmentKind::iter().collect();
+            for kind in kinds.iter().rev() {
+                formatter.push_config_str(*kind, "content");
+            }
+
+            let dot = formatter.to_dot(true, Indent::flat(1));
+            let positions: Vec<usize> = kinds
+                .iter()
+                .map(|kind| {
+                    dot.find(&format!("/* Configuration for {} vertices. */", kind))
+                        .expect(&format!("Missing configuration block for {:?}", kind))
+                })
+                .collect();
+            let mut sorted_positions = positions.clone();
+            sorted_positions.sort();
+            assert_eq!(positions, sorted_positions);
+        }
+    }
+}
+
+impl ToDot for Metadata {
+    /// Get SWC metadata as DOT graph attributes.
+    ///
+    /// Returns an empty `String` if there is no metadata.
+    fn to_dot(&self, leading_newline: bool, indent: Indent) -> String {
+        let mut metadata_buf = StringBuffer::new(leading_newline, indent, 256);
+        if !self.is_empty() {
+            metadata_buf.push_str("graph [");
+            for (i, (key, value)) in self.iter().enumerate() {
+                if i > 0 {
+                    metadata_buf.push_str(", ");
+                }
+                metadata_buf.push_str(&format!("{}={}", quote(key), quote(value)));
+            }
+            metadata_buf.push_str("];");
+        }
+        return metadata_buf.to_string();
+    }
+}
+
+/// Get the comment lines of an SWC file as a DOT block comment.
+///
+/// Returns an empty `String` if there are no comments.
+fn header_comment_to_dot(comments: &[String], indent: Indent) -> String {
+    let mut header = String::with_capacity(64 * comments.len());
+    if !comments.is_empty() {
+        let indent_str = get_indent(indent.main);
+        header.push_str(&format!("\n{}/* SWC header:", indent_str));
+        for comment in comments {
+            // Make sure the comment can't close the block early.
+            header.push_str(&format!("\n{} * {}", indent_str, comment.replace("*/", "* /")));
+        }
+        header.push_str(&format!("\n{} */", indent_str));
+    }
+    return header;
+}
+
+/// Format a number with at most `max_decimals` decimal places and no trailing zeros.
+pub fn format_number(value: f64, max_decimals: usize) -> String {
+    let formatted = format!("{:.*}", max_decimals, value);
+    let trimmed = if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &formatted
+    };
+    match trimmed {
+        "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+/// Quote a string for use as a DOT ID.
+///
+/// Backslashes are escaped as well as double quotes, so that a value ending
+/// in a backslash can't escape the closing quote.
+pub fn quote(string: &str) -> String {
+    format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod metadata_todot_tests {
+    use super::*;
+    use crate::test_utils::{get_test_config, graph_from_swc};
+
+    #[test]
+    fn format_number_is_compact() {
+        assert_eq!(format_number(1.25, 4), "1.25");
+        assert_eq!(format_number(100.0, 3), "100");
+        assert_eq!(format_number(0.33333, 2), "0.33");
+        assert_eq!(format_number(-0.0001, 3), "0");
+        assert_eq!(format_number(120.0, 0), "120");
+    }
+
+    #[test]
+    fn quote_escapes_double_quotes() {
+        assert_eq!(quote("a \"b\""), "\"a \\\"b\\\"\"");
+    }
+
+    #[test]
+    fn quote_escapes_backslashes() {
+        assert_eq!(quote("C:\\data\\"), "\"C:\\\\data\\\\\"");
+    }
+
+    #[test]
+    fn header_metadata_is_written_as_graph_attributes() {
+        let swc = "# CREATURE rat\n\
+                   # ORIGINAL_SOURCE C:\\data\\\n\
+                   1 1 0 0 0 1 -1\n";
+        let dot = graph_from_swc(swc).to_dot(false, Indent::zero(), &get_test_config());
+        assert!(
+            dot.contains("graph [\"CREATURE\"=\"rat\", \"ORIGINAL_SOURCE\"=\"C:\\\\data\\\\\"];")
+        );
+    }
+
+    #[test]
+    fn empty_header_yields_empty_string() {
+        assert_eq!(header_comment_to_dot(&[], Indent::flat(1)), "");
+    }
+
+    #[test]
+    fn header_comment_can_not_be_closed_early() {
+        let header = header_comment_to_dot(&["# a */ b".to_string()], Indent::flat(1));
+        assert_eq!(header.matches("*/").count(), 1);
+        assert!(header.ends_with("*/"));
     }
 }
 
@@ -246,18 +903,83 @@ impl ToDot for ShortTree {
     ///
     /// Rooted trees of depth 1 can be written in one line in DOT.
     fn to_dot(&self, leading_newline: bool, indent: Indent) -> String {
-        let mut tree_buf = StringBuffer::new(leading_newline, indent, 128);
-
-        tree_buf.push_str(&self.get_root_id().to_string());
-        match self.get_child_ids().len() {
-            0 => {}
-            1 => tree_buf.push_str(&format!(" -- {}", self.get_child_ids()[0])),
-            _ => tree_buf.push_str(&format!(
-                " -- {{{}}}",
-                self.get_child_ids().iter().format(", ")
-            )),
+        short_tree_to_dot(self, EdgeDirection::Undirected, leading_newline, indent)
+    }
+}
+
+/// Get DOT representation of a rooted tree of depth 1 with edges in a given direction.
+fn short_tree_to_dot(
+    short_tree: &ShortTree,
+    direction: EdgeDirection,
+    leading_newline: bool,
+    indent: Indent,
+) -> String {
+    let mut tree_buf = StringBuffer::new(leading_newline, indent, 128);
+
+    let root_str = short_tree.get_root_id().to_string();
+    let children_str = match short_tree.get_child_ids().len() {
+        0 => None,
+        1 => Some(short_tree.get_child_ids()[0].to_string()),
+        _ => Some(format!(
+            "{{{}}}",
+            short_tree.get_child_ids().iter().format(", ")
+        )),
+    };
+    match (children_str, direction) {
+        (None, _) => tree_buf.push_str(&root_str),
+        (Some(children_str), EdgeDirection::ChildToParent) => tree_buf.push_str(&format!(
+            "{} {} {}",
+            children_str,
+            direction.get_edge_op(),
+            root_str
+        )),
+        (Some(children_str), _) => tree_buf.push_str(&format!(
+            "{} {} {}",
+            root_str,
+            direction.get_edge_op(),
+            children_str
+        )),
+    }
+    tree_buf.push_str(";");
+    return tree_buf.to_string();
+}
+
+#[cfg(test)]
+mod short_tree_todot_tests {
+    use super::*;
+
+    #[test]
+    fn undirected() {
+        let short_tree = ShortTree::new(1, vec![2, 3]);
+        assert_eq!(short_tree.to_dot(false, Indent::zero()), "1 -- {2, 3};");
+    }
+
+    #[test]
+    fn parent_to_child() {
+        let short_tree = ShortTree::new(1, vec![2]);
+        assert_eq!(
+            short_tree_to_dot(&short_tree, EdgeDirection::ParentToChild, false, Indent::zero()),
+            "1 -> 2;"
+        );
+    }
+
+    #[test]
+    fn child_to_parent() {
+        let short_tree = ShortTree::new(1, vec![2, 3]);
+        assert_eq!(
+            short_tree_to_dot(&short_tree, EdgeDirection::ChildToParent, false, Indent::zero()),
+            "{2, 3} -> 1;"
+        );
+    }
+
+    #[test]
+    fn leaf_has_no_edge() {
+        let short_tree = ShortTree::new(4, Vec::new());
+        for direction in [EdgeDirection::Undirected, EdgeDirection::ChildToParent].iter() {
+            assert_eq!(
+                short_tree_to_dot(&short_tree, *direction, false, Indent::zero()),
+                "4;"
+            );
         }
-        tree_buf.push_str(";");
-        return tree_buf.to_string();
     }
 }
@@ -0,0 +1,88 @@
+#![allow(clippy::redundant_field_names)]
+
+use std::collections::BTreeMap;
+
+use crate::components::{Attributes, Graph, Vertex};
+use crate::writer::{get_edge_attributes_with_chain, EdgeDirection};
+
+/// Vertices and edges of a graph in the order they are written by formats
+/// that list one after the other, like GML and Pajek.
+pub struct Network<'a> {
+    pub directed: bool,
+    /// Vertices in order of id.
+    pub vertices: Vec<&'a Vertex>,
+    pub edges: Vec<NetworkEdge>,
+}
+
+/// An edge between two vertices of a `Network`.
+pub struct NetworkEdge {
+    /// Position of the source vertex in `Network::vertices`.
+    pub source: usize,
+    /// Position of the target vertex in `Network::vertices`.
+    pub target: usize,
+    /// Length of the segment, or of the whole chain in collapsed graphs (see
+    /// `Graph::get_segment()`).
+    pub length: f64,
+    /// Attributes of the edge, including chain measurements (see
+    /// `get_edge_attributes_with_chain()`).
+    pub attributes: Attributes,
+}
+
+impl<'a> Network<'a> {
+    /// Get the vertices and parent-child edges of a graph.
+    ///
+    /// Undirected and parent-to-child edges go from parent to child, and
+    /// child-to-parent edges go the other way.
+    pub fn new(graph: &'a Graph, direction: EdgeDirection) -> Network<'a> {
+        let vertices: Vec<&Vertex> = graph.iter_vertices().map(|(_, vertex)| vertex).collect();
+        let positions: BTreeMap<usize, usize> = vertices
+            .iter()
+            .enumerate()
+            .map(|(position, vertex)| (vertex.get_id(), position))
+            .collect();
+        let edges = graph
+            .iter_edges()
+            .map(|(parent_id, child_id)| {
+                let (source_id, target_id) = match direction {
+                    EdgeDirection::ChildToParent => (child_id, parent_id),
+                    _ => (parent_id, child_id),
+                };
+                NetworkEdge {
+                    source: positions[&source_id],
+                    target: positions[&target_id],
+                    length: graph.get_segment(parent_id, child_id).length,
+                    attributes: get_edge_attributes_with_chain(graph, parent_id, child_id),
+                }
+            })
+            .collect();
+        Network {
+            directed: direction != EdgeDirection::Undirected,
+            vertices: vertices,
+            edges: edges,
+        }
+    }
+}
+
+#[cfg(test)]
+mod network_tests {
+    use super::*;
+    use crate::test_utils::graph_from_swc;
+
+    #[test]
+    fn edges_refer_to_vertex_positions() {
+        let swc = "3 1 0 0 0 1 -1\n7 3 0 1 0 1 3\n9 3 0 2 0 1 7\n";
+        let graph = graph_from_swc(swc);
+
+        let network = Network::new(&graph, EdgeDirection::Undirected);
+        let ids: Vec<usize> = network.vertices.iter().map(|v| v.get_id()).collect();
+        assert_eq!(ids, vec![3, 7, 9]);
+        let edges: Vec<(usize, usize)> =
+            network.edges.iter().map(|e| (e.source, e.target)).collect();
+        assert_eq!(edges, vec![(0, 1), (1, 2)]);
+        assert!(!network.directed);
+
+        let network = Network::new(&graph, EdgeDirection::ChildToParent);
+        assert_eq!((network.edges[0].source, network.edges[0].target), (1, 0));
+        assert!(network.directed);
+    }
+}
@@ -0,0 +1,223 @@
+#![allow(clippy::needless_return)]
+
+use crate::analysis::get_path_distances;
+use crate::components::{Attributes, Graph};
+use crate::writer::format_number;
+use crate::writer::json::quote_json;
+use crate::writer::EdgeDirection;
+
+/// Get a `String` representation of an object as JSON.
+pub trait ToJSON {
+    fn to_json(&self, direction: EdgeDirection) -> String;
+}
+
+impl ToJSON for Graph {
+    /// Get the graph in the node-link format used by NetworkX and D3.
+    ///
+    /// Each node has the id, kind, position and radius of its compartment,
+    /// its branch order, Strahler order and path distance from the root
+    /// (along whole chains in collapsed graphs), and any attributes set on
+    /// the vertex. Each link has the length, mean radius, surface area and
+    /// volume of its segment (or of its whole chain, with the number of
+    /// compartments in it, in collapsed graphs) and any attributes set on the
+    /// edge. Computed values and attributes set as numbers are written as
+    /// numbers, and other attributes as strings. Attributes with the same name
+    /// as a computed value are left out.
+    fn to_json(&self, direction: EdgeDirection) -> String {
+        let path_distances = get_path_distances(self);
+        let branch_orders = self.get_branch_orders();
+        let strahler_orders = self.get_strahler_orders();
+
+        let nodes: Vec<String> = self
+            .iter_vertices()
+            .map(|(id, vertex)| {
+                let position = vertex.get_position();
+                let fields = vec![
+                    ("id", id.to_string()),
+                    ("kind", quote_json(vertex.get_kind().get_name())),
+                    ("x", position.x.to_string()),
+                    ("y", position.y.to_string()),
+                    ("z", position.z.to_string()),
+                    ("radius", vertex.get_radius().to_string()),
+                    ("branch_order", branch_orders[id].to_string()),
+                    ("strahler_order", strahler_orders[id].to_string()),
+                    ("path_distance", format_number(path_distances[id], 4)),
+                ];
+                format!("    {}", object_to_json(&fields, Some(vertex.get_attributes())))
+            })
+            .collect();
+        let links: Vec<String> = self
+            .iter_edges()
+            .map(|(parent_id, child_id)| {
+                let (source_id, target_id) = match direction {
+                    EdgeDirection::ChildToParent => (child_id, parent_id),
+                    _ => (parent_id, child_id),
+                };
+                let segment = self.get_segment(parent_id, child_id);
+                let mut fields = vec![
+                    ("source", source_id.to_string()),
+                    ("target", target_id.to_string()),
+                    ("length", format_number(segment.length, 4)),
+                ];
+                if let Some(chain) = self.get_chain(parent_id, child_id) {
+                    fields.push(("compartments", chain.compartments.to_string()));
+                }
+                fields.push(("mean_radius", format_number(segment.mean_radius, 4)));
+                fields.push(("surface_area", format_number(segment.surface_area, 4)));
+                fields.push(("volume", format_number(segment.volume, 4)));
+                let attributes = self.get_edge_attributes(parent_id, child_id);
+                format!("    {}", object_to_json(&fields, attributes))
+            })
+            .collect();
+        let comments: Vec<String> = self
+            .get_comments()
+            .iter()
+            .map(|comment| quote_json(comment))
+            .collect();
+
+        return format!(
+            "{{\n  \"directed\": {},\n  \"multigraph\": false,\n  \
+             \"graph\": {{\"comments\": [{}]}},\n  \
+             \"nodes\": [\n{}\n  ],\n  \"links\": [\n{}\n  ]\n}}\n",
+            direction != EdgeDirection::Undirected,
+            comments.join(", "),
+            nodes.join(",\n"),
+            links.join(",\n")
+        );
+    }
+}
+
+/// Get a single-line JSON object with computed fields, given as JSON values,
+/// followed by attributes.
+///
+/// Attributes with the same key as a field are left out.
+fn object_to_json(fields: &[(&str, String)], attributes: Option<&Attributes>) -> String {
+    let mut members: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}: {}", quote_json(key), value))
+        .collect();
+    if let Some(attributes) = attributes {
+        for (key, value) in attributes.iter() {
+            if !fields.iter().any(|(field, _)| field == key) {
+                // Numbers are formatted by `format_number()`, which JSON accepts.
+                let value = if attributes.is_number(key) {
+                    value.clone()
+                } else {
+                    quote_json(value)
+                };
+                members.push(format!("{}: {}", quote_json(key), value));
+            }
+        }
+    }
+    return format!("{{{}}}", members.join(", "));
+}
+
+#[cfg(test)]
+mod node_link_tests {
+    use super::*;
+    use crate::test_utils::{get_test_graph, graph_from_swc};
+
+    #[test]
+    fn nodes_have_compartment_data_and_links_have_segment_data() {
+        let expected = "{\n  \"directed\": false,\n  \"multigraph\": false,\n  \
+            \"graph\": {\"comments\": []},\n  \"nodes\": [\n    \
+            {\"id\": 1, \"kind\": \"soma\", \"x\": 0, \"y\": 0, \"z\": 0, \"radius\": 2, \
+            \"branch_order\": 0, \"strahler_order\": 2, \"path_distance\": 0},\n    \
+            {\"id\": 2, \"kind\": \"dendrite\", \"x\": 0, \"y\": 3, \"z\": 0, \"radius\": 1, \
+            \"branch_order\": 1, \"strahler_order\": 1, \"path_distance\": 3},\n    \
+            {\"id\": 3, \"kind\": \"dendrite\", \"x\": 4, \"y\": 3, \"z\": 0, \"radius\": 0.5, \
+            \"branch_order\": 1, \"strahler_order\": 1, \"path_distance\": 7},\n    \
+            {\"id\": 4, \"kind\": \"axon\", \"x\": 0, \"y\": -4, \"z\": 0, \"radius\": 1, \
+            \"branch_order\": 1, \"strahler_order\": 1, \"path_distance\": 4}\n  ],\n  \
+            \"links\": [\n    \
+            {\"source\": 1, \"target\": 2, \"length\": 3, \"mean_radius\": 1.5, \
+            \"surface_area\": 29.8038, \"volume\": 21.9911},\n    \
+            {\"source\": 1, \"target\": 4, \"length\": 4, \"mean_radius\": 1.5, \
+            \"surface_area\": 38.8594, \"volume\": 29.3215},\n    \
+            {\"source\": 2, \"target\": 3, \"length\": 4, \"mean_radius\": 0.75, \
+            \"surface_area\": 18.9962, \"volume\": 7.3304}\n  ]\n}\n";
+        assert_eq!(get_test_graph().to_json(EdgeDirection::Undirected), expected);
+    }
+
+    #[test]
+    fn comments_are_graph_data() {
+        let graph = graph_from_swc("# A \"quoted\" comment\n1 1 0 0 0 2 -1\n");
+        let expected = "{\n  \"directed\": true,\n  \"multigraph\": false,\n  \
+            \"graph\": {\"comments\": [\"# A \\\"quoted\\\" comment\"]},\n  \"nodes\": [\n    \
+            {\"id\": 1, \"kind\": \"soma\", \"x\": 0, \"y\": 0, \"z\": 0, \"radius\": 2, \
+            \"branch_order\": 0, \"strahler_order\": 1, \"path_distance\": 0}\n  ],\n  \
+            \"links\": [\n\n  ]\n}\n";
+        assert_eq!(graph.to_json(EdgeDirection::ParentToChild), expected);
+    }
+
+    #[test]
+    fn collapsed_links_have_chain_data() {
+        let json = get_test_graph().collapse().to_json(EdgeDirection::ChildToParent);
+        let links = &json[json.find("  \"links\"").unwrap()..];
+        assert_eq!(
+            links,
+            "  \"links\": [\n    \
+             {\"source\": 3, \"target\": 1, \"length\": 7, \"compartments\": 2, \
+             \"mean_radius\": 1.0714, \"surface_area\": 48.8, \"volume\": 29.3215},\n    \
+             {\"source\": 4, \"target\": 1, \"length\": 4, \"compartments\": 1, \
+             \"mean_radius\": 1.5, \"surface_area\": 38.8594, \"volume\": 29.3215}\n  ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn computed_values_take_precedence_over_attributes() {
+        let mut graph = get_test_graph();
+        graph.set_vertex_attribute(3, "label", "tip".to_string());
+        graph.set_vertex_attribute(3, "id", "7".to_string());
+        graph.set_vertex_number(3, "path_distance", 1.0);
+        graph.set_edge_number(2, 3, "length", 1.0);
+        let expected = "{\n  \"directed\": false,\n  \"multigraph\": false,\n  \
+            \"graph\": {\"comments\": []},\n  \"nodes\": [\n    \
+            {\"id\": 1, \"kind\": \"soma\", \"x\": 0, \"y\": 0, \"z\": 0, \"radius\": 2, \
+            \"branch_order\": 0, \"strahler_order\": 2, \"path_distance\": 0},\n    \
+            {\"id\": 2, \"kind\": \"dendrite\", \"x\": 0, \"y\": 3, \"z\": 0, \"radius\": 1, \
+            \"branch_order\": 1, \"strahler_order\": 1, \"path_distance\": 3},\n    \
+            {\"id\": 3, \"kind\": \"dendrite\", \"x\": 4, \"y\": 3, \"z\": 0, \"radius\": 0.5, \
+            \"branch_order\": 1, \"strahler_order\": 1, \"path_distance\": 7, \
+            \"label\": \"tip\"},\n    \
+            {\"id\": 4, \"kind\": \"axon\", \"x\": 0, \"y\": -4, \"z\": 0, \"radius\": 1, \
+            \"branch_order\": 1, \"strahler_order\": 1, \"path_distance\": 4}\n  ],\n  \
+            \"links\": [\n    \
+            {\"source\": 1, \"target\": 2, \"length\": 3, \"mean_radius\": 1.5, \
+            \"surface_area\": 29.8038, \"volume\": 21.9911},\n    \
+            {\"source\": 1, \"target\": 4, \"length\": 4, \"mean_radius\": 1.5, \
+            \"surface_area\": 38.8594, \"volume\": 29.3215},\n    \
+            {\"source\": 2, \"target\": 3, \"length\": 4, \"mean_radius\": 0.75, \
+            \"surface_area\": 18.9962, \"volume\": 7.3304}\n  ]\n}\n";
+        assert_eq!(graph.to_json(EdgeDirection::Undirected), expected);
+    }
+
+    #[test]
+    fn attributes_are_numbers_only_if_set_as_numbers() {
+        let mut graph = get_test_graph();
+        graph.set_vertex_attribute(3, "label", "007".to_string());
+        graph.set_vertex_number(3, "sholl_shell", 1.0);
+        graph.set_edge_attribute(2, 3, "weight", "1e3".to_string());
+        graph.set_edge_number(2, 3, "len", 0.5);
+        let expected = "{\n  \"directed\": false,\n  \"multigraph\": false,\n  \
+            \"graph\": {\"comments\": []},\n  \"nodes\": [\n    \
+            {\"id\": 1, \"kind\": \"soma\", \"x\": 0, \"y\": 0, \"z\": 0, \"radius\": 2, \
+            \"branch_order\": 0, \"strahler_order\": 2, \"path_distance\": 0},\n    \
+            {\"id\": 2, \"kind\": \"dendrite\", \"x\": 0, \"y\": 3, \"z\": 0, \"radius\": 1, \
+            \"branch_order\": 1, \"strahler_order\": 1, \"path_distance\": 3},\n    \
+            {\"id\": 3, \"kind\": \"dendrite\", \"x\": 4, \"y\": 3, \"z\": 0, \"radius\": 0.5, \
+            \"branch_order\": 1, \"strahler_order\": 1, \"path_distance\": 7, \
+            \"label\": \"007\", \"sholl_shell\": 1},\n    \
+            {\"id\": 4, \"kind\": \"axon\", \"x\": 0, \"y\": -4, \"z\": 0, \"radius\": 1, \
+            \"branch_order\": 1, \"strahler_order\": 1, \"path_distance\": 4}\n  ],\n  \
+            \"links\": [\n    \
+            {\"source\": 1, \"target\": 2, \"length\": 3, \"mean_radius\": 1.5, \
+            \"surface_area\": 29.8038, \"volume\": 21.9911},\n    \
+            {\"source\": 1, \"target\": 4, \"length\": 4, \"mean_radius\": 1.5, \
+            \"surface_area\": 38.8594, \"volume\": 29.3215},\n    \
+            {\"source\": 2, \"target\": 3, \"length\": 4, \"mean_radius\": 0.75, \
+            \"surface_area\": 18.9962, \"volume\": 7.3304, \"weight\": \"1e3\", \
+            \"len\": 0.5}\n  ]\n}\n";
+        assert_eq!(graph.to_json(EdgeDirection::Undirected), expected);
+    }
+}
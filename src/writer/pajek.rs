@@ -0,0 +1,138 @@
+#![allow(clippy::needless_return)]
+
+use crate::components::Graph;
+use crate::writer::format_number;
+use crate::writer::network::Network;
+use crate::writer::EdgeDirection;
+
+/// Get a `String` representation of an object in Pajek NET format.
+pub trait ToPajek {
+    fn to_pajek(&self, direction: EdgeDirection) -> String;
+}
+
+impl ToPajek for Graph {
+    /// Get a Pajek network with a vertex per compartment and an edge (or arc,
+    /// if directed) per parent-child pair.
+    ///
+    /// Pajek numbers vertices from 1 in order of id, so each vertex is
+    /// labelled by its kind and id, eg `"dendrite 12"`. Positions are scaled
+    /// into the unit cube Pajek draws in, keeping their proportions, with y
+    /// pointing up. Edges are weighted by the length of their segment, or of
+    /// their whole chain in collapsed graphs.
+    fn to_pajek(&self, direction: EdgeDirection) -> String {
+        let network = Network::new(self, direction);
+        let mut pajek = String::with_capacity(64 * self.len());
+
+        let positions: Vec<[f64; 3]> = network
+            .vertices
+            .iter()
+            .map(|vertex| {
+                let position = vertex.get_position();
+                [position.x, position.y, position.z]
+            })
+            .collect();
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for position in positions.iter() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+        let extent = (0..3).fold(0.0, |extent: f64, axis| extent.max(max[axis] - min[axis]));
+
+        pajek.push_str(&format!("*Vertices {}\n", network.vertices.len()));
+        for (i, vertex) in network.vertices.iter().enumerate() {
+            let position = positions[i];
+            let coordinates: Vec<String> = (0..3)
+                .map(|axis| {
+                    if extent <= 0.0 {
+                        return "0.5".to_string();
+                    }
+                    let coordinate = if axis == 1 {
+                        (max[axis] - position[axis]) / extent
+                    } else {
+                        (position[axis] - min[axis]) / extent
+                    };
+                    format_number(coordinate, 4)
+                })
+                .collect();
+            pajek.push_str(&format!(
+                "{} \"{} {}\" {}\n",
+                i + 1,
+                vertex.get_kind().get_name(),
+                vertex.get_id(),
+                coordinates.join(" ")
+            ));
+        }
+
+        pajek.push_str(if network.directed {
+            "*Arcs\n"
+        } else {
+            "*Edges\n"
+        });
+        for edge in network.edges.iter() {
+            pajek.push_str(&format!(
+                "{} {} {}\n",
+                edge.source + 1,
+                edge.target + 1,
+                format_number(edge.length, 4)
+            ));
+        }
+        return pajek;
+    }
+}
+
+#[cfg(test)]
+mod pajek_tests {
+    use super::*;
+    use crate::test_utils::{get_test_graph, graph_from_swc};
+
+    #[test]
+    fn vertices_are_numbered_from_one() {
+        let graph = graph_from_swc("10 1 0 0 0 2 -1\n20 3 0 4 0 1 10\n30 2 2 0 0 1 10\n");
+        let pajek = graph.to_pajek(EdgeDirection::Undirected);
+        assert_eq!(
+            pajek,
+            "*Vertices 3\n\
+             1 \"soma 10\" 0 1 0\n\
+             2 \"dendrite 20\" 0 0 0\n\
+             3 \"axon 30\" 0.5 1 0\n\
+             *Edges\n\
+             1 2 4\n\
+             1 3 2\n"
+        );
+    }
+
+    #[test]
+    fn directed_edges_are_weighted_by_segment_length() {
+        let mut graph = get_test_graph();
+        graph.set_edge_attribute(1, 2, "length", "7".to_string());
+        assert_eq!(
+            graph.to_pajek(EdgeDirection::ChildToParent),
+            "*Vertices 4\n\
+             1 \"soma 1\" 0 0.4286 0\n\
+             2 \"dendrite 2\" 0 0 0\n\
+             3 \"dendrite 3\" 0.5714 0 0\n\
+             4 \"axon 4\" 0 1 0\n\
+             *Arcs\n\
+             2 1 3\n\
+             4 1 4\n\
+             3 2 4\n"
+        );
+    }
+
+    #[test]
+    fn collapsed_edges_are_weighted_by_chain_length() {
+        assert_eq!(
+            get_test_graph().collapse().to_pajek(EdgeDirection::Undirected),
+            "*Vertices 3\n\
+             1 \"soma 1\" 0 0.4286 0\n\
+             2 \"dendrite 3\" 0.5714 0 0\n\
+             3 \"axon 4\" 0 1 0\n\
+             *Edges\n\
+             1 2 7\n\
+             1 3 4\n"
+        );
+    }
+}
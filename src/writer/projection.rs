@@ -0,0 +1,166 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::swc_parser::Point;
+use crate::writer::format_number;
+
+/// Projection of 3D compartment positions onto the 2D drawing plane.
+///
+/// Projected positions are in points (1/72 inch), which is what Graphviz
+/// expects in `pos` attributes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Projection {
+    /// Rows of the 2x3 view matrix mapping `(x, y, z)` onto `(x', y')`.
+    matrix: [[f64; 3]; 2],
+    /// Number of points per unit of SWC distance (usually microns).
+    scale: f64,
+}
+
+impl Projection {
+    /// Project onto a plane spanned by two rows of a view matrix.
+    pub fn from_matrix(matrix: [[f64; 3]; 2]) -> Projection {
+        Projection {
+            matrix: matrix,
+            scale: 1.0,
+        }
+    }
+
+    /// Project onto the xy plane (view from above).
+    pub fn xy() -> Projection {
+        Projection::from_matrix([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]])
+    }
+
+    /// Project onto the xz plane (view from the front).
+    pub fn xz() -> Projection {
+        Projection::from_matrix([[1.0, 0.0, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    /// Project onto the yz plane (view from the side).
+    pub fn yz() -> Projection {
+        Projection::from_matrix([[0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    /// Set the number of points per unit of SWC distance.
+    pub fn with_scale(mut self, scale: f64) -> Projection {
+        self.scale = scale;
+        return self;
+    }
+
+    /// Get the number of points per unit of SWC distance.
+    pub fn get_scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Get the position of a point in the drawing plane, in points.
+    pub fn project(&self, point: &Point) -> (f64, f64) {
+        let project_onto =
+            |row: &[f64; 3]| self.scale * (row[0] * point.x + row[1] * point.y + row[2] * point.z);
+        return (project_onto(&self.matrix[0]), project_onto(&self.matrix[1]));
+    }
+
+    /// Get a Graphviz `pos` value that pins a point in place, eg `1.5,-2!`.
+    pub fn to_pos(&self, point: &Point) -> String {
+        let (x, y) = self.project(point);
+        return format!("{},{}!", format_number(x, 3), format_number(y, 3));
+    }
+}
+
+impl FromStr for Projection {
+    type Err = ProjectionParseError;
+
+    /// Parse `xy`, `xz`, `yz`, or six comma-separated numbers giving the rows
+    /// of a custom 2x3 view matrix (eg `1,0,0,0,0.7,0.7`).
+    fn from_str(s: &str) -> Result<Projection, ProjectionParseError> {
+        match s {
+            "xy" => Ok(Projection::xy()),
+            "xz" => Ok(Projection::xz()),
+            "yz" => Ok(Projection::yz()),
+            _ => {
+                let mut elements = Vec::with_capacity(6);
+                for token in s.split(',') {
+                    match token.trim().parse::<f64>() {
+                        Ok(element) if element.is_finite() => elements.push(element),
+                        _ => return Err(ProjectionParseError(s.to_string())),
+                    }
+                }
+                if elements.len() != 6 {
+                    return Err(ProjectionParseError(s.to_string()));
+                }
+                Ok(Projection::from_matrix([
+                    [elements[0], elements[1], elements[2]],
+                    [elements[3], elements[4], elements[5]],
+                ]))
+            }
+        }
+    }
+}
+
+/// A projection that is neither a known plane nor a 2x3 matrix.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProjectionParseError(String);
+
+impl fmt::Display for ProjectionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expected `xy`, `xz`, `yz` or six comma-separated numbers, got `{}`",
+            self.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod projection_tests {
+    use super::*;
+
+    fn get_test_point() -> Point {
+        Point {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        }
+    }
+
+    #[test]
+    fn planes() {
+        let point = get_test_point();
+        assert_eq!(Projection::xy().project(&point), (1.0, 2.0));
+        assert_eq!(Projection::xz().project(&point), (1.0, 3.0));
+        assert_eq!(Projection::yz().project(&point), (2.0, 3.0));
+    }
+
+    #[test]
+    fn scale() {
+        let point = get_test_point();
+        assert_eq!(Projection::xy().with_scale(2.0).project(&point), (2.0, 4.0));
+    }
+
+    #[test]
+    fn parse_plane() {
+        assert_eq!("xz".parse::<Projection>(), Ok(Projection::xz()));
+    }
+
+    #[test]
+    fn parse_custom_matrix() {
+        let projection = "0, 0, 1, 1, 1, 0".parse::<Projection>().unwrap();
+        assert_eq!(projection.project(&get_test_point()), (3.0, 3.0));
+    }
+
+    #[test]
+    fn parse_wrong_number_of_elements_is_error() {
+        assert!("1,0,0,0,1".parse::<Projection>().is_err());
+        assert!("zx".parse::<Projection>().is_err());
+    }
+
+    #[test]
+    fn pos_is_pinned_and_compact() {
+        let point = Point {
+            x: 0.1 + 0.2,
+            y: -0.0001,
+            z: 0.0,
+        };
+        assert_eq!(Projection::xy().to_pos(&point), "0.3,0!");
+    }
+}
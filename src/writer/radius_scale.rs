@@ -0,0 +1,169 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::components::Attributes;
+use crate::writer::format_number;
+
+/// How compartment radius is turned into the size of a drawn vertex or edge.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RadiusMapping {
+    /// Size is proportional to radius.
+    Linear,
+    /// Size is proportional to `ln(1 + radius)`, which keeps a large soma
+    /// from dwarfing thin distal dendrites.
+    Log,
+    /// Size is proportional to radius, but kept within a fixed range.
+    Clamped,
+}
+
+impl FromStr for RadiusMapping {
+    type Err = RadiusScaleParseError;
+
+    fn from_str(s: &str) -> Result<RadiusMapping, RadiusScaleParseError> {
+        match s {
+            "linear" => Ok(RadiusMapping::Linear),
+            "log" => Ok(RadiusMapping::Log),
+            "clamped" => Ok(RadiusMapping::Clamped),
+            _ => Err(RadiusScaleParseError(format!(
+                "expected `linear`, `log` or `clamped`, got `{}`",
+                s
+            ))),
+        }
+    }
+}
+
+/// Scale used to derive vertex `width`/`height` and edge `penwidth` from
+/// compartment radius.
+///
+/// Sizes are diameters in points (1/72 inch), so with the default factor of 1
+/// vertices are drawn at the same scale as `--pos` positions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RadiusScale {
+    mapping: RadiusMapping,
+    /// Number of points per unit of SWC distance (usually microns).
+    factor: f64,
+    /// Smallest and largest allowed size in points for `RadiusMapping::Clamped`.
+    range: (f64, f64),
+}
+
+impl RadiusScale {
+    pub fn new(mapping: RadiusMapping) -> RadiusScale {
+        RadiusScale {
+            mapping: mapping,
+            factor: 1.0,
+            range: (0.5, 10.0),
+        }
+    }
+
+    /// Set the number of points per unit of SWC distance.
+    pub fn with_factor(mut self, factor: f64) -> RadiusScale {
+        self.factor = factor;
+        return self;
+    }
+
+    /// Set the smallest and largest size in points for `RadiusMapping::Clamped`.
+    pub fn with_range(mut self, min: f64, max: f64) -> RadiusScale {
+        self.range = (min, max);
+        return self;
+    }
+
+    /// Get the drawn diameter in points of a compartment with a given radius.
+    pub fn get_size(&self, radius: f64) -> f64 {
+        let radius = radius.max(0.0);
+        match self.mapping {
+            RadiusMapping::Linear => 2.0 * self.factor * radius,
+            RadiusMapping::Log => 2.0 * self.factor * radius.ln_1p(),
+            RadiusMapping::Clamped => (2.0 * self.factor * radius)
+                .max(self.range.0)
+                .min(self.range.1),
+        }
+    }
+
+    /// Get vertex size attributes for a compartment with a given radius.
+    ///
+    /// Graphviz measures vertex sizes in inches.
+    pub fn get_vertex_attributes(&self, radius: f64) -> Attributes {
+        let size = format_number(self.get_size(radius) / 72.0, 4);
+        let mut attributes = Attributes::new();
+        attributes.insert("width", size.clone());
+        attributes.insert("height", size);
+        attributes.insert("fixedsize", "true".to_string());
+        return attributes;
+    }
+
+    /// Get edge attributes for the segment ending in a compartment with a given radius.
+    pub fn get_edge_attributes(&self, radius: f64) -> Attributes {
+        let mut attributes = Attributes::new();
+        attributes.insert("penwidth", format_number(self.get_size(radius), 4));
+        return attributes;
+    }
+}
+
+/// A radius scale option that could not be parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RadiusScaleParseError(String);
+
+impl fmt::Display for RadiusScaleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod radius_scale_tests {
+    use super::*;
+
+    #[test]
+    fn linear() {
+        let scale = RadiusScale::new(RadiusMapping::Linear).with_factor(2.0);
+        assert_eq!(scale.get_size(1.5), 6.0);
+    }
+
+    #[test]
+    fn log() {
+        let scale = RadiusScale::new(RadiusMapping::Log);
+        assert_eq!(scale.get_size(0.0), 0.0);
+        assert!((scale.get_size(std::f64::consts::E - 1.0) - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn clamped() {
+        let scale = RadiusScale::new(RadiusMapping::Clamped).with_range(1.0, 4.0);
+        assert_eq!(scale.get_size(0.1), 1.0);
+        assert_eq!(scale.get_size(1.5), 3.0);
+        assert_eq!(scale.get_size(100.0), 4.0);
+    }
+
+    #[test]
+    fn thicker_compartments_are_larger() {
+        for mapping in [RadiusMapping::Linear, RadiusMapping::Log].iter() {
+            let scale = RadiusScale::new(*mapping);
+            assert!(scale.get_size(2.0) > scale.get_size(0.5));
+        }
+    }
+
+    #[test]
+    fn vertex_size_is_in_inches() {
+        let scale = RadiusScale::new(RadiusMapping::Linear);
+        let attributes = scale.get_vertex_attributes(18.0);
+        assert_eq!(attributes.get("width"), Some(&"0.5".to_string()));
+        assert_eq!(attributes.get("height"), Some(&"0.5".to_string()));
+    }
+
+    #[test]
+    fn edge_penwidth_is_in_points() {
+        let scale = RadiusScale::new(RadiusMapping::Linear);
+        assert_eq!(
+            scale.get_edge_attributes(0.25).get("penwidth"),
+            Some(&"0.5".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_mapping() {
+        assert_eq!("log".parse::<RadiusMapping>(), Ok(RadiusMapping::Log));
+        assert!("quadratic".parse::<RadiusMapping>().is_err());
+    }
+}
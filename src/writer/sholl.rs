@@ -0,0 +1,184 @@
+#![allow(clippy::needless_return)]
+
+use crate::analysis::ShollProfile;
+use crate::swc_parser::SWCCompartmentKind;
+use crate::writer::csv::quote_csv;
+use crate::writer::format_number;
+use crate::writer::json::{json_records_to_string, quote_json};
+
+/// Format for Sholl profiles.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ShollFormat {
+    /// A row per sphere of each file.
+    Csv,
+    /// An object per file, or an array of them if there are several files.
+    Json,
+}
+
+/// Get the Sholl profiles of one or more files in a given format.
+///
+/// Each record is the name of a file and its profile. If `by_kind` is set,
+/// intersections of each kind of neurite are listed separately as well.
+pub fn sholl_to_string(
+    records: &[(String, ShollProfile)],
+    format: ShollFormat,
+    by_kind: bool,
+) -> String {
+    match format {
+        ShollFormat::Csv => sholl_to_csv(records, by_kind),
+        ShollFormat::Json => {
+            let objects: Vec<String> = records
+                .iter()
+                .map(|(file_name, profile)| sholl_profile_to_json(file_name, profile, by_kind))
+                .collect();
+            json_records_to_string(&objects)
+        }
+    }
+}
+
+/// Kinds of neurite that can cross a Sholl sphere.
+fn get_neurite_kinds() -> Vec<SWCCompartmentKind> {
+    SWCCompartmentKind::iter()
+        .filter(|kind| *kind != SWCCompartmentKind::Soma)
+        .collect()
+}
+
+fn sholl_to_csv(records: &[(String, ShollProfile)], by_kind: bool) -> String {
+    let mut header = vec!["file", "radius", "intersections"];
+    if by_kind {
+        header.extend(get_neurite_kinds().iter().map(|kind| kind.get_name()));
+    }
+    let mut csv = format!("{}\n", header.join(","));
+    for (file_name, profile) in records {
+        for (i, radius) in profile.radii.iter().enumerate() {
+            let mut row = vec![
+                quote_csv(file_name),
+                format_number(*radius, 4),
+                profile.intersections[i].to_string(),
+            ];
+            if by_kind {
+                for kind in get_neurite_kinds() {
+                    row.push(
+                        profile
+                            .kinds
+                            .get(&kind)
+                            .map_or(0, |counts| counts[i])
+                            .to_string(),
+                    );
+                }
+            }
+            csv.push_str(&format!("{}\n", row.join(",")));
+        }
+    }
+    return csv;
+}
+
+fn sholl_profile_to_json(file_name: &str, profile: &ShollProfile, by_kind: bool) -> String {
+    let mut fields = vec![
+        format!("\"file\": {}", quote_json(file_name)),
+        format!(
+            "\"center\": [{}, {}, {}]",
+            format_number(profile.center.x, 4),
+            format_number(profile.center.y, 4),
+            format_number(profile.center.z, 4)
+        ),
+        format!(
+            "\"radii\": [{}]",
+            profile
+                .radii
+                .iter()
+                .map(|radius| format_number(*radius, 4))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        format!(
+            "\"intersections\": {}",
+            counts_to_json(&profile.intersections)
+        ),
+    ];
+    if by_kind {
+        let kinds: Vec<String> = profile
+            .kinds
+            .iter()
+            .map(|(kind, counts)| {
+                format!(
+                    "    {}: {}",
+                    quote_json(kind.get_name()),
+                    counts_to_json(counts)
+                )
+            })
+            .collect();
+        if kinds.is_empty() {
+            fields.push("\"kinds\": {}".to_string());
+        } else {
+            fields.push(format!("\"kinds\": {{\n{}\n  }}", kinds.join(",\n")));
+        }
+    }
+    return format!("{{\n  {}\n}}", fields.join(",\n  "));
+}
+
+fn counts_to_json(counts: &[usize]) -> String {
+    let counts: Vec<String> = counts.iter().map(|count| count.to_string()).collect();
+    return format!("[{}]", counts.join(", "));
+}
+
+#[cfg(test)]
+mod sholl_writer_tests {
+    use super::*;
+    use crate::test_utils::get_test_graph;
+
+    fn get_test_records() -> Vec<(String, ShollProfile)> {
+        let profile =
+            ShollProfile::measure(&get_test_graph(), 2.0).expect("Could not measure test profile.");
+        return vec![("a.swc".to_string(), profile.clone()), ("b, c.swc".to_string(), profile)];
+    }
+
+    #[test]
+    fn csv_has_a_row_per_sphere() {
+        let csv = sholl_to_string(&get_test_records(), ShollFormat::Csv, false);
+        assert_eq!(
+            csv,
+            "file,radius,intersections\n\
+             a.swc,2,2\n\
+             a.swc,4,2\n\
+             \"b, c.swc\",2,2\n\
+             \"b, c.swc\",4,2\n"
+        );
+    }
+
+    #[test]
+    fn csv_by_kind() {
+        let csv = sholl_to_string(&get_test_records()[..1], ShollFormat::Csv, true);
+        assert_eq!(
+            csv,
+            "file,radius,intersections,undefined,axon,dendrite,apicaldendrite,custom\n\
+             a.swc,2,2,0,1,1,0,0\n\
+             a.swc,4,2,0,1,1,0,0\n"
+        );
+    }
+
+    #[test]
+    fn json_has_an_object_per_file() {
+        let json = sholl_to_string(&get_test_records(), ShollFormat::Json, false);
+        assert_eq!(
+            json,
+            "[\n\
+             {\n  \"file\": \"a.swc\",\n  \"center\": [0, 0, 0],\n  \"radii\": [2, 4],\n  \
+             \"intersections\": [2, 2]\n},\n\
+             {\n  \"file\": \"b, c.swc\",\n  \"center\": [0, 0, 0],\n  \"radii\": [2, 4],\n  \
+             \"intersections\": [2, 2]\n}\n\
+             ]\n"
+        );
+    }
+
+    #[test]
+    fn json_by_kind() {
+        let json = sholl_to_string(&get_test_records()[..1], ShollFormat::Json, true);
+        assert_eq!(
+            json,
+            "{\n  \"file\": \"a.swc\",\n  \"center\": [0, 0, 0],\n  \"radii\": [2, 4],\n  \
+             \"intersections\": [2, 2],\n  \"kinds\": {\n    \"axon\": [1, 1],\n    \
+             \"dendrite\": [1, 1]\n  }\n}\n"
+        );
+    }
+}
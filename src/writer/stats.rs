@@ -0,0 +1,408 @@
+#![allow(clippy::needless_return)]
+
+use crate::analysis::{KindMorphometrics, Morphometrics};
+use crate::swc_parser::{Point, SWCCompartmentKind};
+use crate::writer::csv::quote_csv;
+use crate::writer::format_number;
+use crate::writer::json::{json_records_to_string, quote_json};
+
+/// Format for `stats` output.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StatsFormat {
+    /// Aligned tables for reading in a terminal.
+    Text,
+    /// An object per file, or an array of them if there are several files.
+    Json,
+    /// A row per file, with columns for every kind of compartment.
+    Csv,
+}
+
+/// Get the measurements of one or more files in a given format.
+///
+/// Each record is the name of a file and its measurements.
+pub fn stats_to_string(records: &[(String, Morphometrics)], format: StatsFormat) -> String {
+    match format {
+        StatsFormat::Text => stats_to_text(records),
+        StatsFormat::Json => stats_to_json(records),
+        StatsFormat::Csv => stats_to_csv(records),
+    }
+}
+
+fn stats_to_text(records: &[(String, Morphometrics)]) -> String {
+    let mut text = String::new();
+    for (i, (file_name, metrics)) in records.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        text.push_str(&format!("{}\n", file_name));
+
+        let mut summary = vec![
+            vec!["compartments".to_string(), metrics.compartments.to_string()],
+            vec![
+                "total length".to_string(),
+                format_number(metrics.total_length, 3),
+            ],
+            vec![
+                "branch points".to_string(),
+                metrics.branch_points.to_string(),
+            ],
+            vec!["terminals".to_string(), metrics.terminals.to_string()],
+            vec!["stems".to_string(), metrics.stems.to_string()],
+            vec![
+                "max branch order".to_string(),
+                metrics.max_branch_order.to_string(),
+            ],
+            vec![
+                "max strahler order".to_string(),
+                metrics.max_strahler_order.to_string(),
+            ],
+            vec![
+                "max path distance".to_string(),
+                format_number(metrics.max_path_distance, 3),
+            ],
+            vec![
+                "total surface area".to_string(),
+                format_number(metrics.total_surface_area, 3),
+            ],
+            vec![
+                "total volume".to_string(),
+                format_number(metrics.total_volume, 3),
+            ],
+            vec![
+                "soma radius".to_string(),
+                metrics
+                    .soma_radius
+                    .map_or("-".to_string(), |radius| format_number(radius, 3)),
+            ],
+        ];
+        if let Some((min, max)) = &metrics.bounding_box {
+            summary.push(vec![
+                "bounding box".to_string(),
+                format!("{} to {}", point_to_text(min), point_to_text(max)),
+            ]);
+        }
+        text.push_str(&table_to_text(&summary));
+
+        if !metrics.kinds.is_empty() {
+            let mut kinds = vec![vec![
+                "kind".to_string(),
+                "compartments".to_string(),
+                "length".to_string(),
+                "surface area".to_string(),
+                "volume".to_string(),
+                "branch points".to_string(),
+                "terminals".to_string(),
+            ]];
+            for (kind, kind_metrics) in metrics.kinds.iter() {
+                kinds.push(vec![
+                    kind.get_name().to_string(),
+                    kind_metrics.compartments.to_string(),
+                    format_number(kind_metrics.length, 3),
+                    format_number(kind_metrics.surface_area, 3),
+                    format_number(kind_metrics.volume, 3),
+                    kind_metrics.branch_points.to_string(),
+                    kind_metrics.terminals.to_string(),
+                ]);
+            }
+            text.push('\n');
+            text.push_str(&table_to_text(&kinds));
+        }
+    }
+    return text;
+}
+
+/// Get rows of cells as indented text with aligned columns.
+fn table_to_text(rows: &[Vec<String>]) -> String {
+    let column_count = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..column_count)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let mut text = String::new();
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        text.push_str(&format!("  {}\n", cells.join("  ").trim_end()));
+    }
+    return text;
+}
+
+fn point_to_text(point: &Point) -> String {
+    format!(
+        "({}, {}, {})",
+        format_number(point.x, 3),
+        format_number(point.y, 3),
+        format_number(point.z, 3)
+    )
+}
+
+fn stats_to_json(records: &[(String, Morphometrics)]) -> String {
+    let objects: Vec<String> = records
+        .iter()
+        .map(|(file_name, metrics)| morphometrics_to_json(file_name, metrics))
+        .collect();
+    return json_records_to_string(&objects);
+}
+
+fn morphometrics_to_json(file_name: &str, metrics: &Morphometrics) -> String {
+    let mut fields = vec![
+        format!("\"file\": {}", quote_json(file_name)),
+        format!("\"compartments\": {}", metrics.compartments),
+        format!(
+            "\"total_length\": {}",
+            format_number(metrics.total_length, 4)
+        ),
+        format!("\"branch_points\": {}", metrics.branch_points),
+        format!("\"terminals\": {}", metrics.terminals),
+        format!("\"stems\": {}", metrics.stems),
+        format!("\"max_branch_order\": {}", metrics.max_branch_order),
+        format!("\"max_strahler_order\": {}", metrics.max_strahler_order),
+        format!(
+            "\"max_path_distance\": {}",
+            format_number(metrics.max_path_distance, 4)
+        ),
+        format!(
+            "\"total_surface_area\": {}",
+            format_number(metrics.total_surface_area, 4)
+        ),
+        format!(
+            "\"total_volume\": {}",
+            format_number(metrics.total_volume, 4)
+        ),
+        format!(
+            "\"soma_radius\": {}",
+            metrics
+                .soma_radius
+                .map_or("null".to_string(), |radius| format_number(radius, 4))
+        ),
+    ];
+    fields.push(match &metrics.bounding_box {
+        Some((min, max)) => format!(
+            "\"bounding_box\": {{\"min\": {}, \"max\": {}}}",
+            point_to_json(min),
+            point_to_json(max)
+        ),
+        None => "\"bounding_box\": null".to_string(),
+    });
+    let kinds: Vec<String> = metrics
+        .kinds
+        .iter()
+        .map(|(kind, kind_metrics)| {
+            format!(
+                "    {}: {{\"compartments\": {}, \"length\": {}, \"surface_area\": {}, \
+                 \"volume\": {}, \"branch_points\": {}, \"terminals\": {}}}",
+                quote_json(kind.get_name()),
+                kind_metrics.compartments,
+                format_number(kind_metrics.length, 4),
+                format_number(kind_metrics.surface_area, 4),
+                format_number(kind_metrics.volume, 4),
+                kind_metrics.branch_points,
+                kind_metrics.terminals
+            )
+        })
+        .collect();
+    if kinds.is_empty() {
+        fields.push("\"kinds\": {}".to_string());
+    } else {
+        fields.push(format!("\"kinds\": {{\n{}\n  }}", kinds.join(",\n")));
+    }
+    return format!("{{\n  {}\n}}", fields.join(",\n  "));
+}
+
+fn point_to_json(point: &Point) -> String {
+    format!(
+        "[{}, {}, {}]",
+        format_number(point.x, 4),
+        format_number(point.y, 4),
+        format_number(point.z, 4)
+    )
+}
+
+fn stats_to_csv(records: &[(String, Morphometrics)]) -> String {
+    let mut header: Vec<String> = [
+        "file",
+        "compartments",
+        "total_length",
+        "branch_points",
+        "terminals",
+        "stems",
+        "max_branch_order",
+        "max_strahler_order",
+        "max_path_distance",
+        "total_surface_area",
+        "total_volume",
+        "soma_radius",
+        "min_x",
+        "min_y",
+        "min_z",
+        "max_x",
+        "max_y",
+        "max_z",
+    ]
+    .iter()
+    .map(|column| column.to_string())
+    .collect();
+    for kind in SWCCompartmentKind::iter() {
+        for column in [
+            "compartments",
+            "length",
+            "surface_area",
+            "volume",
+            "branch_points",
+            "terminals",
+        ]
+        .iter()
+        {
+            header.push(format!("{}_{}", kind.get_name(), column));
+        }
+    }
+
+    let mut csv = format!("{}\n", header.join(","));
+    for (file_name, metrics) in records {
+        let mut row = vec![
+            quote_csv(file_name),
+            metrics.compartments.to_string(),
+            format_number(metrics.total_length, 4),
+            metrics.branch_points.to_string(),
+            metrics.terminals.to_string(),
+            metrics.stems.to_string(),
+            metrics.max_branch_order.to_string(),
+            metrics.max_strahler_order.to_string(),
+            format_number(metrics.max_path_distance, 4),
+            format_number(metrics.total_surface_area, 4),
+            format_number(metrics.total_volume, 4),
+            metrics
+                .soma_radius
+                .map_or(String::new(), |radius| format_number(radius, 4)),
+        ];
+        match &metrics.bounding_box {
+            Some((min, max)) => {
+                for value in [min.x, min.y, min.z, max.x, max.y, max.z].iter() {
+                    row.push(format_number(*value, 4));
+                }
+            }
+            None => row.extend(vec![String::new(); 6]),
+        }
+        for kind in SWCCompartmentKind::iter() {
+            let empty = KindMorphometrics::default();
+            let kind_metrics = metrics.kinds.get(&kind).unwrap_or(&empty);
+            row.push(kind_metrics.compartments.to_string());
+            row.push(format_number(kind_metrics.length, 4));
+            row.push(format_number(kind_metrics.surface_area, 4));
+            row.push(format_number(kind_metrics.volume, 4));
+            row.push(kind_metrics.branch_points.to_string());
+            row.push(kind_metrics.terminals.to_string());
+        }
+        csv.push_str(&format!("{}\n", row.join(",")));
+    }
+    return csv;
+}
+
+#[cfg(test)]
+mod stats_writer_tests {
+    use super::*;
+    use crate::test_utils::get_test_graph;
+
+    fn get_test_records() -> Vec<(String, Morphometrics)> {
+        let metrics = Morphometrics::measure(&get_test_graph());
+        return vec![
+            ("a.swc".to_string(), metrics.clone()),
+            ("b, c.swc".to_string(), metrics),
+        ];
+    }
+
+    #[test]
+    fn text_lists_kinds() {
+        let text = stats_to_string(&get_test_records()[..1], StatsFormat::Text);
+        assert_eq!(
+            text,
+            "a.swc\n\
+             \x20 compartments        4\n\
+             \x20 total length        11\n\
+             \x20 branch points       0\n\
+             \x20 terminals           2\n\
+             \x20 stems               2\n\
+             \x20 max branch order    1\n\
+             \x20 max strahler order  2\n\
+             \x20 max path distance   7\n\
+             \x20 total surface area  87.659\n\
+             \x20 total volume        58.643\n\
+             \x20 soma radius         2\n\
+             \x20 bounding box        (0, -4, 0) to (4, 3, 0)\n\
+             \n\
+             \x20 kind      compartments  length  surface area  volume  branch points  terminals\n\
+             \x20 soma      1             0       0             0       0              0\n\
+             \x20 axon      1             4       38.859        29.322  0              1\n\
+             \x20 dendrite  2             7       48.8          29.322  0              1\n"
+        );
+    }
+
+    #[test]
+    fn json_is_array_for_several_files() {
+        let object = "{\n  \"file\": \"FILE\",\n  \"compartments\": 4,\n  \
+            \"total_length\": 11,\n  \"branch_points\": 0,\n  \"terminals\": 2,\n  \
+            \"stems\": 2,\n  \"max_branch_order\": 1,\n  \"max_strahler_order\": 2,\n  \
+            \"max_path_distance\": 7,\n  \"total_surface_area\": 87.6594,\n  \
+            \"total_volume\": 58.6431,\n  \"soma_radius\": 2,\n  \
+            \"bounding_box\": {\"min\": [0, -4, 0], \"max\": [4, 3, 0]},\n  \"kinds\": {\n    \
+            \"soma\": {\"compartments\": 1, \"length\": 0, \"surface_area\": 0, \"volume\": 0, \
+            \"branch_points\": 0, \"terminals\": 0},\n    \
+            \"axon\": {\"compartments\": 1, \"length\": 4, \"surface_area\": 38.8594, \
+            \"volume\": 29.3215, \"branch_points\": 0, \"terminals\": 1},\n    \
+            \"dendrite\": {\"compartments\": 2, \"length\": 7, \"surface_area\": 48.8, \
+            \"volume\": 29.3215, \"branch_points\": 0, \"terminals\": 1}\n  }\n}";
+        let records = get_test_records();
+        assert_eq!(
+            stats_to_string(&records[..1], StatsFormat::Json),
+            format!("{}\n", object.replace("FILE", "a.swc"))
+        );
+        assert_eq!(
+            stats_to_string(&records, StatsFormat::Json),
+            format!(
+                "[\n{},\n{}\n]\n",
+                object.replace("FILE", "a.swc"),
+                object.replace("FILE", "b, c.swc")
+            )
+        );
+    }
+
+    #[test]
+    fn csv_has_one_row_per_file() {
+        let header = "file,compartments,total_length,branch_points,terminals,stems,\
+                      max_branch_order,max_strahler_order,max_path_distance,\
+                      total_surface_area,total_volume,soma_radius,\
+                      min_x,min_y,min_z,max_x,max_y,max_z,\
+                      undefined_compartments,undefined_length,undefined_surface_area,\
+                      undefined_volume,undefined_branch_points,undefined_terminals,\
+                      soma_compartments,soma_length,soma_surface_area,\
+                      soma_volume,soma_branch_points,soma_terminals,\
+                      axon_compartments,axon_length,axon_surface_area,\
+                      axon_volume,axon_branch_points,axon_terminals,\
+                      dendrite_compartments,dendrite_length,dendrite_surface_area,\
+                      dendrite_volume,dendrite_branch_points,dendrite_terminals,\
+                      apicaldendrite_compartments,apicaldendrite_length,\
+                      apicaldendrite_surface_area,apicaldendrite_volume,\
+                      apicaldendrite_branch_points,apicaldendrite_terminals,\
+                      custom_compartments,custom_length,custom_surface_area,\
+                      custom_volume,custom_branch_points,custom_terminals";
+        let values = "4,11,0,2,2,1,2,7,87.6594,58.6431,2,0,-4,0,4,3,0,\
+                      0,0,0,0,0,0,\
+                      1,0,0,0,0,0,\
+                      1,4,38.8594,29.3215,0,1,\
+                      2,7,48.8,29.3215,0,1,\
+                      0,0,0,0,0,0,\
+                      0,0,0,0,0,0";
+        assert_eq!(
+            stats_to_string(&get_test_records(), StatsFormat::Csv),
+            format!("{}\na.swc,{}\n\"b, c.swc\",{}\n", header, values, values)
+        );
+    }
+}
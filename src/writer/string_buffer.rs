@@ -1,3 +1,5 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
 /// A `String` buffer that will appear to be empty if it has never been modified.
 #[derive(Clone, Debug)]
 pub struct StringBuffer {
@@ -11,6 +13,7 @@ pub struct StringBuffer {
 
 impl StringBuffer {
     /// Create a new `StringBuffer`.
+    #[allow(clippy::single_char_add_str)]
     pub fn new(leading_newline: bool, indent: Indent, capacity: usize) -> StringBuffer {
         let mut buf = String::with_capacity((32 + INDENT_SIZE * indent.first) as usize + capacity);
 
@@ -43,6 +46,7 @@ impl StringBuffer {
     /// Insert a newline into the `StringBuffer`.
     ///
     /// Does not mark the buffer as modified.
+    #[allow(clippy::single_char_add_str)]
     pub fn newline(&mut self) {
         self.buf.push_str("\n");
         self.buf.push_str(&get_indent(self.indent_level));
@@ -51,6 +55,7 @@ impl StringBuffer {
     }
 
     /// Push `&str` onto the end of `StringBuffer`, but don't flag the buffer as modified.
+    #[allow(clippy::collapsible_if)]
     pub fn weak_push_str(&mut self, string: &str) {
         // Start on a new line if we will run out of room on the current one,
         // unless we're already at the start of a line.
@@ -73,6 +78,7 @@ impl StringBuffer {
     /// Get contents of `StringBuffer`.
     ///
     /// Returns an empty `String` if the buffer has not been marked as modified.
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         if self.has_been_written_to {
             let mut result = self.buf.clone();
@@ -295,6 +301,7 @@ impl Indent {
     ///
     /// assert_eq!(absolute_indent, relative_indent);
     /// ```
+    #[allow(dead_code)]
     pub fn absolute_first_line(first_line_level: u8, main_indent_level: u8) -> Indent {
         Indent{
             first: first_line_level,
@@ -312,6 +319,7 @@ impl Indent {
     ///
     /// assert_eq!(absolute_indent, relative_indent);
     /// ```
+    #[allow(dead_code)]
     pub fn relative_first_line(first_line_level: i8, main_indent_level: u8) -> Indent {
         if (first_line_level as i32) + (main_indent_level as i32) < 0 {
             panic!(
@@ -385,6 +393,7 @@ mod indent_tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_cast)]
     fn relative_first_line() {
         for (first_indent_level, main_indent_level) in [(0, 0), (-2, 5), (7, 9)].iter() {
             let indent = Indent::relative_first_line(*first_indent_level, *main_indent_level);
@@ -0,0 +1,209 @@
+#![allow(clippy::needless_return)]
+
+use crate::components::{Graph, Vertex};
+use crate::swc_parser::SWCCompartment;
+
+/// Formatting options for SWC output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SWCFormat {
+    /// Number of digits after the decimal point for positions and radii.
+    ///
+    /// If `None`, numbers are written with as many digits as needed to
+    /// represent them exactly.
+    pub precision: Option<usize>,
+    /// String placed between columns.
+    pub separator: String,
+}
+
+impl Default for SWCFormat {
+    fn default() -> SWCFormat {
+        SWCFormat {
+            precision: None,
+            separator: " ".to_string(),
+        }
+    }
+}
+
+impl SWCFormat {
+    fn format_float(&self, value: f64) -> String {
+        match self.precision {
+            Some(precision) => format!("{:.*}", precision, value),
+            None => value.to_string(),
+        }
+    }
+}
+
+/// Get a `String` representation of an object in SWC format.
+pub trait ToSWC {
+    fn to_swc(&self, format: &SWCFormat) -> String;
+}
+
+impl ToSWC for SWCCompartment {
+    /// Get a single SWC compartment line, without a trailing newline.
+    fn to_swc(&self, format: &SWCFormat) -> String {
+        let parent_id = match self.parent_id {
+            Some(parent_id) => parent_id.to_string(),
+            None => "-1".to_string(),
+        };
+        let columns = [
+            self.id.to_string(),
+            self.get_kind_code().to_string(),
+            format.format_float(self.position.x),
+            format.format_float(self.position.y),
+            format.format_float(self.position.z),
+            format.format_float(self.radius),
+            parent_id,
+        ];
+        columns.join(&format.separator)
+    }
+}
+
+impl ToSWC for Vertex {
+    /// Get a single SWC compartment line, without a trailing newline.
+    fn to_swc(&self, format: &SWCFormat) -> String {
+        self.get_compartment().to_swc(format)
+    }
+}
+
+impl ToSWC for Graph {
+    /// Get the contents of an SWC file with one line per vertex, in order of
+    /// id, after the comments of the file the graph was read from.
+    fn to_swc(&self, format: &SWCFormat) -> String {
+        let mut swc_string = String::with_capacity(48 * self.len());
+        for comment in self.get_comments() {
+            swc_string.push_str(comment);
+            swc_string.push('\n');
+        }
+        for (_, vertex) in self.iter_vertices() {
+            swc_string.push_str(&vertex.to_swc(format));
+            swc_string.push('\n');
+        }
+        return swc_string;
+    }
+}
+
+#[cfg(test)]
+mod compartment_toswc_tests {
+    use super::*;
+    use crate::swc_parser::{Point, SWCCompartmentKind};
+
+    fn get_test_compartment(parent_id: Option<usize>) -> SWCCompartment {
+        SWCCompartment::new(
+            12,
+            SWCCompartmentKind::ApicalDendrite,
+            Point {
+                x: 0.5,
+                y: -2.0,
+                z: 3.25,
+            },
+            6.86102,
+            parent_id,
+        )
+    }
+
+    #[test]
+    fn root_has_negative_parent() {
+        let compartment = get_test_compartment(None);
+        assert_eq!(
+            compartment.to_swc(&SWCFormat::default()),
+            "12 4 0.5 -2 3.25 6.86102 -1"
+        );
+    }
+
+    #[test]
+    fn child_has_parent_id() {
+        let compartment = get_test_compartment(Some(11));
+        assert_eq!(
+            compartment.to_swc(&SWCFormat::default()),
+            "12 4 0.5 -2 3.25 6.86102 11"
+        );
+    }
+
+    #[test]
+    fn custom_kind_code() {
+        let mut compartment = get_test_compartment(None);
+        compartment.kind = SWCCompartmentKind::Custom;
+        compartment.custom_code = 7;
+        assert!(compartment
+            .to_swc(&SWCFormat::default())
+            .starts_with("12 7 "));
+    }
+
+    #[test]
+    fn precision() {
+        let compartment = get_test_compartment(Some(11));
+        let format = SWCFormat {
+            precision: Some(2),
+            ..SWCFormat::default()
+        };
+        assert_eq!(
+            compartment.to_swc(&format),
+            "12 4 0.50 -2.00 3.25 6.86 11"
+        );
+    }
+
+    #[test]
+    fn separator() {
+        let compartment = get_test_compartment(Some(11));
+        let format = SWCFormat {
+            separator: "\t".to_string(),
+            ..SWCFormat::default()
+        };
+        assert_eq!(
+            compartment.to_swc(&format),
+            "12\t4\t0.5\t-2\t3.25\t6.86102\t11"
+        );
+    }
+}
+
+#[cfg(test)]
+mod graph_toswc_tests {
+    use super::*;
+    use crate::test_utils::{graph_from_swc, parse_swc};
+
+    #[test]
+    fn parse_write_parse_round_trip() {
+        let swc = "# ORIGINAL_SOURCE tracer\n\
+                   # a comment\n\
+                   1 1 0 0 0 2.5 -1\n\
+                   2 7 0 3.25 -1 1 1\n\
+                   3 12 1 4 0 0.5 2\n\
+                   4 3 -1 4 0 0.5 2\n";
+        let neuron = parse_swc(swc);
+        let written = graph_from_swc(swc).to_swc(&SWCFormat::default());
+        assert_eq!(written, swc);
+
+        let reparsed = parse_swc(&written);
+        assert_eq!(reparsed.get_comments(), neuron.get_comments());
+        let compartments: Vec<&SWCCompartment> = neuron.iter().map(|(_, c)| c).collect();
+        let reparsed_compartments: Vec<&SWCCompartment> =
+            reparsed.iter().map(|(_, c)| c).collect();
+        assert_eq!(reparsed_compartments, compartments);
+        assert_eq!(reparsed_compartments[1].get_kind_code(), 7);
+        assert_eq!(reparsed_compartments[2].get_kind_code(), 12);
+    }
+
+    #[test]
+    fn tab_separated_output_can_be_read_back() {
+        let swc = "1 1 0 0 0 2 -1\n2 3 0 1 0 1 1\n";
+        let neuron = parse_swc(swc);
+        let format = SWCFormat {
+            separator: "\t".to_string(),
+            ..SWCFormat::default()
+        };
+        let written = graph_from_swc(swc).to_swc(&format);
+        let reparsed = parse_swc(&written);
+        assert_eq!(
+            reparsed.iter().collect::<Vec<_>>(),
+            neuron.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn collapsed_graph_can_be_read_back() {
+        let swc = "1 1 0 0 0 2 -1\n2 3 0 1 0 1 1\n3 3 0 2 0 1 2\n4 3 0 3 0 1 3\n";
+        let written = graph_from_swc(swc).collapse().to_swc(&SWCFormat::default());
+        assert_eq!(written, "1 1 0 0 0 2 -1\n4 3 0 3 0 1 1\n");
+        assert_eq!(parse_swc(&written).iter().count(), 2);
+    }
+}
@@ -0,0 +1,248 @@
+#![allow(clippy::needless_return, clippy::redundant_static_lifetimes)]
+
+use crate::analysis::get_path_distances;
+use crate::components::{Attributes, Graph};
+use crate::writer::csv::quote_delimited;
+use crate::writer::{format_number, get_edge_attributes_with_chain};
+
+/// Columns of edge lists that are written for every edge.
+static EDGE_COLUMNS: &'static [&'static str] = &[
+    "parent",
+    "child",
+    "length",
+    "radius_parent",
+    "radius_child",
+    "kind",
+];
+/// Columns of node tables that are written for every vertex.
+static NODE_COLUMNS: &'static [&'static str] = &[
+    "id",
+    "kind",
+    "x",
+    "y",
+    "z",
+    "radius",
+    "parent",
+    "branch_order",
+    "strahler_order",
+    "path_distance",
+];
+
+/// Get delimited table representations of an object, such as CSV or TSV.
+///
+/// Positions, radii, lengths and distances are rounded to 4 decimal places.
+/// Fields that contain the delimiter are quoted, numbers included.
+pub trait ToTable {
+    /// Get a table with a header row and a row per edge.
+    fn to_edge_list(&self, delimiter: &str) -> String;
+    /// Get a table with a header row and a row per vertex.
+    fn to_node_table(&self, delimiter: &str) -> String;
+}
+
+impl ToTable for Graph {
+    /// Get a table with a row per parent-child pair.
+    ///
+    /// Columns are `parent`, `child`, `length` (see `Graph::get_segment()`),
+    /// `radius_parent`, `radius_child` and `kind`, the kind of the child, and
+    /// then a column for each attribute set on any edge, which is empty for
+    /// edges without it.
+    fn to_edge_list(&self, delimiter: &str) -> String {
+        let edge_attributes: Vec<Attributes> = self
+            .iter_edges()
+            .map(|(parent_id, child_id)| get_edge_attributes_with_chain(self, parent_id, child_id))
+            .collect();
+        let attribute_columns = get_attribute_columns(edge_attributes.iter(), EDGE_COLUMNS);
+
+        let mut table = String::with_capacity(32 * self.len());
+        table.push_str(&header_to_table(EDGE_COLUMNS, &attribute_columns, delimiter));
+        for ((parent_id, child_id), attributes) in self.iter_edges().zip(edge_attributes.iter()) {
+            let parent = self.get_vertex(parent_id).unwrap();
+            let child = self.get_vertex(child_id).unwrap();
+            let mut row = vec![
+                parent_id.to_string(),
+                child_id.to_string(),
+                format_number(self.get_segment(parent_id, child_id).length, 4),
+                format_number(parent.get_radius(), 4),
+                format_number(child.get_radius(), 4),
+                child.get_kind().get_name().to_string(),
+            ];
+            for column in attribute_columns.iter() {
+                row.push(attributes.get(column).cloned().unwrap_or_default());
+            }
+            table.push_str(&row_to_table(&row, delimiter));
+        }
+        return table;
+    }
+
+    /// Get a table with a row per compartment.
+    ///
+    /// Columns are the id, kind, position, radius and parent (empty for
+    /// roots) of each compartment, its branch order, Strahler order and path
+    /// distance from the root, and then a column for each attribute set on
+    /// any vertex, which is empty for vertices without it.
+    fn to_node_table(&self, delimiter: &str) -> String {
+        let attribute_columns = get_attribute_columns(
+            self.iter_vertices()
+                .map(|(_, vertex)| vertex.get_attributes()),
+            NODE_COLUMNS,
+        );
+        let branch_orders = self.get_branch_orders();
+        let strahler_orders = self.get_strahler_orders();
+        let path_distances = get_path_distances(self);
+
+        let mut table = String::with_capacity(64 * self.len());
+        table.push_str(&header_to_table(NODE_COLUMNS, &attribute_columns, delimiter));
+        for (id, vertex) in self.iter_vertices() {
+            let position = vertex.get_position();
+            let mut row = vec![
+                id.to_string(),
+                vertex.get_kind().get_name().to_string(),
+                format_number(position.x, 4),
+                format_number(position.y, 4),
+                format_number(position.z, 4),
+                format_number(vertex.get_radius(), 4),
+                vertex
+                    .get_parent_id()
+                    .map_or(String::new(), |parent_id| parent_id.to_string()),
+                branch_orders[id].to_string(),
+                strahler_orders[id].to_string(),
+                format_number(path_distances[id], 4),
+            ];
+            for column in attribute_columns.iter() {
+                row.push(vertex.get_attributes().get(column).cloned().unwrap_or_default());
+            }
+            table.push_str(&row_to_table(&row, delimiter));
+        }
+        return table;
+    }
+}
+
+/// Get the names of attributes in order of first appearance, leaving out
+/// those that already have a column.
+fn get_attribute_columns<'a>(
+    attributes: impl Iterator<Item = &'a Attributes>,
+    columns: &[&str],
+) -> Vec<&'a String> {
+    let mut attribute_columns: Vec<&String> = Vec::new();
+    for attributes in attributes {
+        for (key, _) in attributes.iter() {
+            if !columns.contains(&key.as_str()) && !attribute_columns.contains(&key) {
+                attribute_columns.push(key);
+            }
+        }
+    }
+    return attribute_columns;
+}
+
+/// Get the header row of a table.
+fn header_to_table(columns: &[&str], attribute_columns: &[&String], delimiter: &str) -> String {
+    let header: Vec<String> = columns
+        .iter()
+        .copied()
+        .chain(attribute_columns.iter().map(|column| column.as_str()))
+        .map(|column| column.to_string())
+        .collect();
+    return row_to_table(&header, delimiter);
+}
+
+/// Get a row of a table, quoting fields that contain the delimiter.
+fn row_to_table(fields: &[String], delimiter: &str) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| quote_delimited(field, delimiter))
+        .collect();
+    return format!("{}\n", fields.join(delimiter));
+}
+
+#[cfg(test)]
+mod table_tests {
+    use super::*;
+    use crate::test_utils::{get_test_graph, graph_from_swc};
+
+    #[test]
+    fn edge_list() {
+        assert_eq!(
+            get_test_graph().to_edge_list(","),
+            "parent,child,length,radius_parent,radius_child,kind\n\
+             1,2,3,2,1,dendrite\n\
+             1,4,4,2,1,axon\n\
+             2,3,4,1,0.5,dendrite\n"
+        );
+    }
+
+    #[test]
+    fn edge_list_of_collapsed_graph_has_chain_measurements() {
+        assert_eq!(
+            get_test_graph().collapse().to_edge_list("\t"),
+            "parent\tchild\tlength\tradius_parent\tradius_child\tkind\t\
+             compartments\tmean_radius\tsurface_area\tvolume\n\
+             1\t3\t7\t2\t0.5\tdendrite\t2\t1.0714\t48.8\t29.3215\n\
+             1\t4\t4\t2\t1\taxon\t1\t1.5\t38.8594\t29.3215\n"
+        );
+    }
+
+    #[test]
+    fn edge_list_has_edge_attributes() {
+        let mut graph = get_test_graph();
+        graph.set_edge_attribute(2, 3, "label", "a\tb".to_string());
+        graph.set_edge_attribute(1, 2, "length", "10".to_string());
+        assert_eq!(
+            graph.to_edge_list("\t"),
+            "parent\tchild\tlength\tradius_parent\tradius_child\tkind\tlabel\n\
+             1\t2\t3\t2\t1\tdendrite\t\n\
+             1\t4\t4\t2\t1\taxon\t\n\
+             2\t3\t4\t1\t0.5\tdendrite\t\"a\tb\"\n"
+        );
+    }
+
+    #[test]
+    fn node_table_has_metrics_and_attributes() {
+        let mut graph = get_test_graph();
+        graph.set_vertex_attribute(3, "label", "a, b".to_string());
+        graph.set_vertex_attribute(3, "branch_order", "9".to_string());
+        graph.set_edge_attribute(2, 3, "length", "10".to_string());
+        assert_eq!(
+            graph.to_node_table(","),
+            "id,kind,x,y,z,radius,parent,branch_order,strahler_order,path_distance,label\n\
+             1,soma,0,0,0,2,,0,2,0,\n\
+             2,dendrite,0,3,0,1,1,1,1,3,\n\
+             3,dendrite,4,3,0,0.5,2,1,1,7,\"a, b\"\n\
+             4,axon,0,-4,0,1,1,1,1,4,\n"
+        );
+    }
+
+    #[test]
+    fn numbers_are_rounded_alike() {
+        let graph = graph_from_swc("1 1 0.123456 0 0 2.000001 -1\n2 3 0.123456 3 0 0.33333 1\n");
+        assert_eq!(
+            graph.to_edge_list(","),
+            "parent,child,length,radius_parent,radius_child,kind\n\
+             1,2,3,2,0.3333,dendrite\n"
+        );
+        assert_eq!(
+            graph.to_node_table(","),
+            "id,kind,x,y,z,radius,parent,branch_order,strahler_order,path_distance\n\
+             1,soma,0.1235,0,0,2,,0,1,0\n\
+             2,dendrite,0.1235,3,0,0.3333,1,1,1,3\n"
+        );
+    }
+
+    #[test]
+    fn fields_containing_the_delimiter_are_quoted() {
+        assert_eq!(
+            get_test_graph().to_edge_list("_"),
+            "parent_child_length_\"radius_parent\"_\"radius_child\"_kind\n\
+             1_2_3_2_1_dendrite\n\
+             1_4_4_2_1_axon\n\
+             2_3_4_1_0.5_dendrite\n"
+        );
+        assert_eq!(
+            get_test_graph().to_node_table("-"),
+            "id-kind-x-y-z-radius-parent-branch_order-strahler_order-path_distance\n\
+             1-soma-0-0-0-2--0-2-0\n\
+             2-dendrite-0-3-0-1-1-1-1-3\n\
+             3-dendrite-4-3-0-0.5-2-1-1-7\n\
+             4-axon-0-\"-4\"-0-1-1-1-1-4\n"
+        );
+    }
+}